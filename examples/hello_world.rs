@@ -3,10 +3,18 @@ use std::error::Error;
 use CrabServe::server::{ CrabServer, Server };
 use CrabServe::database::mongodb::MongoDB;
 use CrabServe::database::db::Database;
+use CrabServe::http_core::{ http_types::HttpMethods, response::Response };
+use CrabServe::router::router::Router;
+
+async fn hello_world() -> Response {
+    Response::new(200).add_body(b"Hello, World!".to_vec())
+}
 
 #[tokio::main(worker_threads = 3)]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let server = CrabServer::new([127, 0, 0, 1], 8080);
+    let server = CrabServer::new([127, 0, 0, 1], 8080).add_router(
+        Router::new("/".to_string()).add_route(HttpMethods::GET, "/", hello_world)
+    );
         match
             server.run(
                 Some(
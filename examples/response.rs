@@ -3,10 +3,25 @@ use std::error::Error;
 use CrabServe::server::{ CrabServer, Server };
 use CrabServe::database::mongodb::MongoDB;
 use CrabServe::database::db::Database;
+use CrabServe::http_core::{ http_types::HttpMethods, response::Response };
+use CrabServe::router::router::Router;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Greeting {
+    message: String,
+}
+
+async fn greeting() -> Response {
+    let greeting = Greeting { message: String::from("Hello from CrabServer") };
+    Response::new(200).set_json_body(&greeting).unwrap()
+}
 
 #[tokio::main(worker_threads = 3)]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let server = CrabServer::new([127, 0, 0, 1], 8080);
+    let server = CrabServer::new([127, 0, 0, 1], 8080).add_router(
+        Router::new("/api".to_string()).add_route(HttpMethods::GET, "/greeting", greeting)
+    );
         match
             server.run(
                 Some(
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub max_header_size: usize,
+    pub max_body_size: usize,
+    pub keep_alive_timeout: Duration,
+    pub read_timeout: Duration,
+    pub max_requests_per_connection: usize,
+    pub tls_handshake_timeout: Duration,
+    pub tls_reload_interval: Option<Duration>,
+    pub http2_max_concurrent_streams: u32,
+    pub http2_initial_window_size: u32,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_header_size: 8 * 1024,
+            max_body_size: 2 * 1024 * 1024,
+            keep_alive_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            max_requests_per_connection: 100,
+            tls_handshake_timeout: Duration::from_secs(10),
+            tls_reload_interval: None,
+            http2_max_concurrent_streams: 100,
+            http2_initial_window_size: 65_535,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_header_size(mut self, max_header_size: usize) -> Self {
+        self.max_header_size = max_header_size;
+        self
+    }
+
+    /// The largest body read into memory before the handler runs.
+    /// `multipart/form-data` bodies are streamed to the handler instead and
+    /// limited by its `MultipartConfig`.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn keep_alive_timeout(mut self, keep_alive_timeout: Duration) -> Self {
+        self.keep_alive_timeout = keep_alive_timeout;
+        self
+    }
+
+    /// How long a started request may go without sending more bytes
+    /// before it is answered with 408.
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn max_requests_per_connection(mut self, max_requests_per_connection: usize) -> Self {
+        self.max_requests_per_connection = max_requests_per_connection;
+        self
+    }
+
+    pub fn tls_handshake_timeout(mut self, tls_handshake_timeout: Duration) -> Self {
+        self.tls_handshake_timeout = tls_handshake_timeout;
+        self
+    }
+
+    /// How often certificate files are checked for changes and reloaded.
+    pub fn tls_reload_interval(mut self, tls_reload_interval: Duration) -> Self {
+        self.tls_reload_interval = Some(tls_reload_interval);
+        self
+    }
+
+    pub fn http2_max_concurrent_streams(mut self, http2_max_concurrent_streams: u32) -> Self {
+        self.http2_max_concurrent_streams = http2_max_concurrent_streams;
+        self
+    }
+
+    /// Flow-control window, in bytes, granted to each HTTP/2 stream for request bodies.
+    pub fn http2_initial_window_size(mut self, http2_initial_window_size: u32) -> Self {
+        self.http2_initial_window_size = http2_initial_window_size;
+        self
+    }
+}
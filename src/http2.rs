@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::poll_fn;
+use std::sync::Arc;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use h2::{ server::SendResponse, RecvStream, SendStream };
+use log::error;
+use percent_encoding::percent_decode_str;
+use tokio::io::{ AsyncRead, AsyncReadExt, AsyncWrite };
+use tokio::sync::watch;
+
+use crate::config::config::ServerConfig;
+use crate::http_core::{
+    extensions::Extensions,
+    headers::HeaderMap,
+    http_types::HttpMethods,
+    request::{ HttpRequest, Request, RequestError },
+    body::Body,
+    body_stream::{ BodySender, BodyStream },
+    multipart::boundary,
+    response::Response,
+};
+use crate::router::middleware::Next;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// What a client speaking HTTP/2 with prior knowledge sends first.
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+// Connection-specific headers have no meaning in HTTP/2 and make clients
+// treat the response as malformed (RFC 9113 section 8.2.2).
+const CONNECTION_HEADERS: [&str; 5] = ["Connection", "Keep-Alive", "Proxy-Connection", "Transfer-Encoding", "Upgrade"];
+
+/// Reads from a cleartext connection until the bytes either make up the
+/// HTTP/2 preface or stop matching it. Returns whether they matched and the
+/// bytes read, which belong to whichever protocol handles the connection.
+pub(crate) async fn sniff_preface<S: AsyncRead + Unpin>(stream: &mut S) -> std::io::Result<(bool, Vec<u8>)> {
+    let mut buffer = vec![0; PREFACE.len()];
+    let mut filled = 0;
+    while filled < PREFACE.len() {
+        let n = stream.read(&mut buffer[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+        if !PREFACE.starts_with(&buffer[..filled]) {
+            break;
+        }
+    }
+    buffer.truncate(filled);
+    Ok((buffer == PREFACE, buffer))
+}
+
+/// Serves an HTTP/2 connection, answering each stream through `app` in a
+/// task of its own. Once `shutdown` turns true the client gets a GOAWAY and
+/// the streams already open are finished before the connection closes.
+pub(crate) async fn serve_connection<S>(
+    stream: S,
+    app: Next,
+    config: Arc<ServerConfig>,
+    mut shutdown: watch::Receiver<bool>
+) -> Result<(), Box<dyn Error>>
+    where S: AsyncRead + AsyncWrite + Unpin
+{
+    let mut connection = h2::server::Builder::new()
+        .max_concurrent_streams(config.http2_max_concurrent_streams)
+        .initial_window_size(config.http2_initial_window_size)
+        .max_header_list_size(config.max_header_size as u32)
+        .handshake::<_, Bytes>(stream).await?;
+
+    let mut shutting_down = false;
+    loop {
+        let accepted = if shutting_down {
+            connection.accept().await
+        } else {
+            // A dropped sender means the server is gone, which is a shutdown too.
+            tokio::select! {
+                accepted = connection.accept() => accepted,
+                _ = shutdown.wait_for(|stop| *stop) => {
+                    shutting_down = true;
+                    connection.graceful_shutdown();
+                    continue;
+                }
+            }
+        };
+
+        match accepted {
+            Some(Ok((request, respond))) => {
+                let app = app.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_stream(request, respond, app, config).await {
+                        error!("Failed to answer HTTP/2 stream: {}", e);
+                    }
+                });
+            }
+            Some(Err(e)) => {
+                return Err(e.into());
+            }
+            None => {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn serve_stream(
+    request: http::Request<RecvStream>,
+    mut respond: SendResponse<Bytes>,
+    app: Next,
+    config: Arc<ServerConfig>
+) -> Result<(), BoxError> {
+    let head_only = request.method() == http::Method::HEAD;
+    let response = match read_request(request, &config).await {
+        Ok(request) => app.run(request).await,
+        Err(response) => response,
+    };
+    send_response(&mut respond, response, head_only).await
+}
+
+async fn read_request(request: http::Request<RecvStream>, config: &ServerConfig) -> Result<Request, Response> {
+    let bad_request = |message: &str| Response::new(400).add_body(message.as_bytes().to_vec());
+    let (parts, mut body) = request.into_parts();
+
+    let raw_path = parts.uri.path();
+    let path = percent_decode_str(raw_path)
+        .decode_utf8()
+        .map_err(|_| bad_request("Invalid percent-encoding in path"))?;
+    let method = parts.method
+        .as_str()
+        .parse::<HttpMethods>()
+        .map_err(|e| bad_request(&RequestError::InvalidMethodError(e.0).to_string()))?;
+
+    // The path is already split from the query, so a decoded `?` stays in it.
+    let mut request = Request {
+        method,
+        path: path.into_owned(),
+        raw_path: raw_path.to_string(),
+        query: parts.uri.query().unwrap_or_default().to_string(),
+        version: "HTTP/2.0".to_string(),
+        headers: convert_headers(&parts.headers).ok_or_else(|| bad_request("Invalid header value"))?,
+        body: Vec::new(),
+        trailers: HeaderMap::new(),
+        params: HashMap::new(),
+        extensions: Extensions::new(),
+    };
+
+    // Handlers look for the host in `Host`, which HTTP/2 carries as `:authority`.
+    if let Some(authority) = parts.uri.authority() {
+        if !request.headers.contains_key("Host") {
+            request.add_header("Host", authority.as_str());
+        }
+    }
+    // HTTP/2 may split cookies into several fields (RFC 9113 section 8.2.3).
+    let cookies: Vec<String> = request.headers
+        .get_all("Cookie")
+        .map(|value| value.as_str().to_string())
+        .collect();
+    if cookies.len() > 1 {
+        request.add_header("Cookie", &cookies.join("; "));
+    }
+
+    // As over HTTP/1.1, multipart uploads are parsed while they arrive.
+    if request.headers.get_str("Content-Type").and_then(boundary).is_some() {
+        let (sender, stream) = BodyStream::channel();
+        request.extensions.insert(stream);
+        tokio::spawn(forward_body(body, sender));
+        return Ok(request);
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| bad_request(&e.to_string()))?;
+        let _ = body.flow_control().release_capacity(chunk.len());
+        if bytes.len() + chunk.len() > config.max_body_size {
+            return Err(Response::new(413).add_body(b"Payload too large".to_vec()));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    if let Ok(Some(trailers)) = body.trailers().await {
+        request.trailers = convert_headers(&trailers).ok_or_else(|| bad_request("Invalid trailer value"))?;
+    }
+
+    Request::set_body_and_content_length(&mut request.headers, &bytes);
+    request.body = bytes;
+    Ok(request)
+}
+
+// Window capacity is only released once the handler has room for a chunk,
+// which keeps a fast client from getting ahead of it.
+async fn forward_body(mut body: RecvStream, sender: BodySender) {
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map(|chunk| chunk.to_vec()).map_err(std::io::Error::other);
+        let length = chunk.as_ref().map_or(0, Vec::len);
+        let failed = chunk.is_err();
+        if sender.send(chunk).await.is_err() || failed {
+            return;
+        }
+        let _ = body.flow_control().release_capacity(length);
+    }
+}
+
+fn convert_headers(headers: &http::HeaderMap) -> Option<HeaderMap> {
+    let mut converted = HeaderMap::new();
+    for (name, value) in headers {
+        converted.try_append(name.as_str(), value.to_str().ok()?).ok()?;
+    }
+    Some(converted)
+}
+
+async fn send_response(respond: &mut SendResponse<Bytes>, response: Response, head_only: bool) -> Result<(), BoxError> {
+    let mut head = http::Response::builder().status(response.status_code().as_u16());
+    for (name, value) in response.headers() {
+        if !CONNECTION_HEADERS.iter().any(|header| name.as_str().eq_ignore_ascii_case(header)) {
+            head = head.header(name.as_str(), value.as_str());
+        }
+    }
+    for (name, value) in response.implicit_headers() {
+        head = head.header(name, value);
+    }
+
+    let empty = head_only || !response.allows_body() || response.body.is_empty();
+    let mut stream = respond.send_response(head.body(())?, empty)?;
+    if empty {
+        return Ok(());
+    }
+
+    match response.body {
+        Body::Bytes(bytes) => send_data(&mut stream, Bytes::from(bytes), true).await,
+        Body::File(file) => {
+            let mut position = 0;
+            while position < file.length() {
+                let chunk = file.read_chunk(position).await?;
+                if chunk.is_empty() {
+                    // As over HTTP/1.1: the announced length can't be met any more.
+                    stream.send_reset(h2::Reason::INTERNAL_ERROR);
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
+                position += chunk.len() as u64;
+                send_data(&mut stream, Bytes::from(chunk), position == file.length()).await?;
+            }
+            Ok(())
+        }
+        Body::Stream(body) => {
+            if let Some(mut chunks) = body.take() {
+                while let Some(chunk) = chunks.next().await {
+                    match chunk {
+                        Ok(chunk) => send_data(&mut stream, Bytes::from(chunk), false).await?,
+                        Err(e) => {
+                            // A reset tells the client the body is incomplete.
+                            stream.send_reset(h2::Reason::INTERNAL_ERROR);
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+            stream.send_data(Bytes::new(), true)?;
+            Ok(())
+        }
+    }
+}
+
+// Waits for the peer to grant flow-control window before each frame, so a
+// slow reader holds the handler's data back instead of it piling up in memory.
+async fn send_data(stream: &mut SendStream<Bytes>, mut data: Bytes, end_of_stream: bool) -> Result<(), BoxError> {
+    while !data.is_empty() {
+        stream.reserve_capacity(data.len());
+        let capacity = match poll_fn(|cx| stream.poll_capacity(cx)).await {
+            Some(capacity) => capacity?,
+            None => {
+                return Err("HTTP/2 stream closed before the body was sent".into());
+            }
+        };
+        if capacity == 0 {
+            continue;
+        }
+        let chunk = data.split_to(capacity.min(data.len()));
+        stream.send_data(chunk, end_of_stream && data.is_empty())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::{ Arc, Mutex };
+use futures_util::stream::{ BoxStream, Stream, StreamExt };
+use serde::{ Serialize, Serializer };
+use tokio::io::{ AsyncWrite, AsyncWriteExt };
+
+use super::response::{ encode_chunk, LAST_CHUNK };
+
+pub(crate) type BoxError = Box<dyn Error + Send + Sync>;
+
+type Chunks = BoxStream<'static, Result<Vec<u8>, BoxError>>;
+
+pub(crate) const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// What a response sends after its head: bytes held in memory, a range of
+/// a file, or chunks produced while the response is being sent.
+///
+/// Files and streams are written as the socket accepts them, so a slow
+/// client holds back reading instead of the body piling up in memory.
+#[derive(Debug, Clone)]
+pub enum Body {
+    Bytes(Vec<u8>),
+    File(FileBody),
+    Stream(StreamBody),
+}
+
+/// A byte range of an open file, sent without reading the whole range
+/// into memory. Reads name their position instead of moving the file's
+/// cursor, so clones of a response can be sent at the same time.
+#[derive(Debug, Clone)]
+pub struct FileBody {
+    file: Arc<std::fs::File>,
+    offset: u64,
+    length: u64,
+}
+
+/// Chunks produced while the response is being sent. Whichever copy of the
+/// response is written first takes them.
+#[derive(Clone)]
+pub struct StreamBody(Arc<Mutex<Option<Chunks>>>);
+
+impl Body {
+    pub fn empty() -> Self {
+        Body::Bytes(Vec::new())
+    }
+
+    /// Sends `length` bytes of `file` starting at `offset`.
+    pub fn file(file: std::fs::File, offset: u64, length: u64) -> Self {
+        Body::File(FileBody { file: Arc::new(file), offset, length })
+    }
+
+    /// Sends each chunk as soon as `chunks` yields it. An error ends the
+    /// response abruptly, so the client can tell it was cut short.
+    pub fn stream<S, E>(chunks: S) -> Self
+        where S: Stream<Item = Result<Vec<u8>, E>> + Send + 'static, E: Into<BoxError>
+    {
+        let chunks = chunks.map(|chunk| chunk.map_err(Into::into)).boxed();
+        Body::Stream(StreamBody(Arc::new(Mutex::new(Some(chunks)))))
+    }
+
+    /// The length in bytes, unknown for streams.
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::File(file) => Some(file.length),
+            Body::Stream(_) => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Body::Stream(_))
+    }
+
+    /// The bytes of an in-memory body.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Reads a file body into memory. Streams can't be read this way, since
+    /// their chunks are only produced while the response is sent.
+    pub(crate) fn to_vec(&self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Body::Bytes(bytes) => Ok(bytes.clone()),
+            Body::File(file) => file.read_all(),
+            Body::Stream(_) => Err(std::io::Error::other("Streamed bodies can only be sent with write_to")),
+        }
+    }
+
+    /// Writes the body in chunked encoding or as is. `length` is the
+    /// `Content-Length` announced for a stream, which it must then match.
+    pub(crate) async fn write_to<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        chunked: bool,
+        length: Option<u64>
+    ) -> std::io::Result<()> {
+        match self {
+            Body::Bytes(bytes) if chunked => {
+                writer.write_all(&encode_chunk(bytes)).await?;
+                writer.write_all(LAST_CHUNK).await
+            }
+            Body::Bytes(bytes) => writer.write_all(bytes).await,
+            Body::File(file) => file.write_to(writer, chunked).await,
+            Body::Stream(stream) =>
+                match stream.take() {
+                    Some(chunks) => write_stream(writer, chunks, chunked, length).await,
+                    None if chunked => writer.write_all(LAST_CHUNK).await,
+                    None => Ok(()),
+                }
+        }
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::empty()
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body::Bytes(text.into_bytes())
+    }
+}
+
+// Only in-memory bytes can be serialized; other bodies show up empty.
+impl Serialize for Body {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_bytes().unwrap_or_default().serialize(serializer)
+    }
+}
+
+impl FileBody {
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    fn read_all(&self) -> std::io::Result<Vec<u8>> {
+        let mut bytes = vec![0; self.length as usize];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match read_at(&self.file, &mut bytes[filled..], self.offset + filled as u64)? {
+                0 => {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                n => {
+                    filled += n;
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Up to `FILE_CHUNK_SIZE` bytes of the range, starting `position` bytes
+    /// into it; empty if the file ends before that.
+    pub(crate) async fn read_chunk(&self, position: u64) -> std::io::Result<Vec<u8>> {
+        let file = self.file.clone();
+        let start = self.offset + position;
+        let length = self.length.saturating_sub(position).min(FILE_CHUNK_SIZE as u64) as usize;
+        tokio::task
+            ::spawn_blocking(move || {
+                let mut chunk = vec![0; length];
+                let n = read_at(&file, &mut chunk, start)?;
+                chunk.truncate(n);
+                Ok(chunk)
+            }).await
+            .map_err(std::io::Error::other)?
+    }
+
+    async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W, chunked: bool) -> std::io::Result<()> {
+        let mut position = 0;
+        while position < self.length {
+            let chunk = self.read_chunk(position).await?;
+            if chunk.is_empty() {
+                // The file shrank after the headers went out; the length can't be taken back.
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            if chunked {
+                writer.write_all(&encode_chunk(&chunk)).await?;
+            } else {
+                writer.write_all(&chunk).await?;
+            }
+            position += chunk.len() as u64;
+        }
+
+        if chunked {
+            writer.write_all(LAST_CHUNK).await?;
+        }
+        Ok(())
+    }
+}
+
+// Reads at an absolute position, leaving the file's shared cursor alone.
+#[cfg(unix)]
+fn read_at(file: &std::fs::File, buffer: &mut [u8], position: u64) -> std::io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buffer, position)
+}
+
+#[cfg(windows)]
+fn read_at(file: &std::fs::File, buffer: &mut [u8], position: u64) -> std::io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buffer, position)
+}
+
+impl StreamBody {
+    pub(crate) fn take(&self) -> Option<Chunks> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl fmt::Debug for StreamBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StreamBody").finish()
+    }
+}
+
+// The next chunk is only asked for once the previous one is written, and
+// each is flushed, since the one after may be a long time coming.
+async fn write_stream<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut chunks: Chunks,
+    chunked: bool,
+    length: Option<u64>
+) -> std::io::Result<()> {
+    let mut written = 0;
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(std::io::Error::other)?;
+        written += chunk.len() as u64;
+        if length.is_some_and(|length| written > length) {
+            return Err(std::io::Error::other("Streamed body is longer than its Content-Length"));
+        }
+        if chunked {
+            writer.write_all(&encode_chunk(&chunk)).await?;
+        } else {
+            writer.write_all(&chunk).await?;
+        }
+        writer.flush().await?;
+    }
+
+    if length.is_some_and(|length| written < length) {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    if chunked {
+        writer.write_all(LAST_CHUNK).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{ Context, Poll };
+use tokio::io::{ AsyncRead, ReadBuf };
+use tokio::sync::mpsc;
+
+pub(crate) type BodySender = mpsc::Sender<std::io::Result<Vec<u8>>>;
+
+/// How many chunks the connection may read ahead of the handler.
+const READ_AHEAD: usize = 4;
+
+/// A request body that is read from the connection while the handler runs,
+/// instead of being buffered before it. The server leaves `multipart/form-data`
+/// bodies in the request's extensions this way, outside `max_body_size`, and
+/// the first extractor to `take` it gets it.
+#[derive(Debug)]
+pub struct BodyStream(Mutex<Option<BodyReader>>);
+
+impl BodyStream {
+    pub(crate) fn channel() -> (BodySender, Self) {
+        let (sender, receiver) = mpsc::channel(READ_AHEAD);
+        (sender, BodyStream(Mutex::new(Some(BodyReader::streamed(receiver)))))
+    }
+
+    pub fn take(&self) -> Option<BodyReader> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The bytes of a request body as they arrive.
+#[derive(Debug)]
+pub struct BodyReader {
+    chunk: Vec<u8>,
+    position: usize,
+    receiver: Option<mpsc::Receiver<std::io::Result<Vec<u8>>>>,
+}
+
+impl BodyReader {
+    fn streamed(receiver: mpsc::Receiver<std::io::Result<Vec<u8>>>) -> Self {
+        Self { chunk: Vec::new(), position: 0, receiver: Some(receiver) }
+    }
+}
+
+/// Reads a body that was already buffered.
+impl From<Vec<u8>> for BodyReader {
+    fn from(body: Vec<u8>) -> Self {
+        Self { chunk: body, position: 0, receiver: None }
+    }
+}
+
+impl AsyncRead for BodyReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        while self.position == self.chunk.len() {
+            let Some(receiver) = self.receiver.as_mut() else {
+                return Poll::Ready(Ok(()));
+            };
+            match receiver.poll_recv(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Ready(None) => {
+                    self.receiver = None;
+                }
+                Poll::Pending => {
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        let n = buf.remaining().min(self.chunk.len() - self.position);
+        buf.put_slice(&self.chunk[self.position..self.position + n]);
+        self.position += n;
+        Poll::Ready(Ok(()))
+    }
+}
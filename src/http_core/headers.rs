@@ -0,0 +1,311 @@
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvalidHeader {
+    #[error("Invalid header name: '{0}'")] InvalidHeaderNameError(String),
+    #[error("Invalid header value: {0:?}")] InvalidHeaderValueError(String),
+}
+
+/// Header field name. Keeps the spelling it was created with but compares
+/// and hashes case-insensitively.
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HeaderName(String);
+
+impl HeaderName {
+    /// Panics if `name` is not a valid token; meant for literals.
+    pub fn from_static(name: &'static str) -> Self {
+        match HeaderName::try_from(name) {
+            Ok(name) => name,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for HeaderName {
+    type Error = InvalidHeader;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        if !name.is_empty() && name.bytes().all(is_token_byte) {
+            Ok(HeaderName(name.to_string()))
+        } else {
+            Err(InvalidHeader::InvalidHeaderNameError(name.to_string()))
+        }
+    }
+}
+
+impl TryFrom<String> for HeaderName {
+    type Error = InvalidHeader;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        HeaderName::try_from(name.as_str())
+    }
+}
+
+impl From<HeaderName> for String {
+    fn from(name: HeaderName) -> Self {
+        name.0
+    }
+}
+
+impl PartialEq for HeaderName {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl PartialEq<str> for HeaderName {
+    fn eq(&self, other: &str) -> bool {
+        self.0.eq_ignore_ascii_case(other)
+    }
+}
+
+impl Hash for HeaderName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for byte in self.0.bytes() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+    }
+}
+
+impl fmt::Display for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Header field value. Rejects CR, LF and NUL so values can never inject
+/// additional header lines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HeaderValue(String);
+
+impl HeaderValue {
+    /// Panics if `value` contains CR, LF or NUL; meant for literals.
+    pub fn from_static(value: &'static str) -> Self {
+        match HeaderValue::try_from(value) {
+            Ok(value) => value,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for HeaderValue {
+    type Error = InvalidHeader;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.bytes().any(|byte| matches!(byte, b'\r' | b'\n' | b'\0')) {
+            Err(InvalidHeader::InvalidHeaderValueError(value.to_string()))
+        } else {
+            Ok(HeaderValue(value.trim().to_string()))
+        }
+    }
+}
+
+impl TryFrom<String> for HeaderValue {
+    type Error = InvalidHeader;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        HeaderValue::try_from(value.as_str())
+    }
+}
+
+impl From<usize> for HeaderValue {
+    fn from(value: usize) -> Self {
+        HeaderValue(value.to_string())
+    }
+}
+
+impl From<HeaderValue> for String {
+    fn from(value: HeaderValue) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq<str> for HeaderValue {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for HeaderValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for HeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Ordered, case-insensitive, multi-valued header collection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderMap {
+    entries: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// First value of `name`.
+    pub fn get(&self, name: &str) -> Option<&HeaderValue> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).map(HeaderValue::as_str)
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a HeaderValue> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.entries.iter().any(|(key, _)| key == name)
+    }
+
+    /// Whether any value of `name`, read as a comma-separated list, contains
+    /// `token` (e.g. `Connection: keep-alive, Upgrade`).
+    pub fn contains_token(&self, name: &str, token: &str) -> bool {
+        self.get_all(name)
+            .flat_map(|value| value.as_str().split(','))
+            .any(|item| item.trim().eq_ignore_ascii_case(token))
+    }
+
+    pub fn content_length(&self) -> Option<usize> {
+        self.get_str("Content-Length").and_then(|value| value.parse().ok())
+    }
+
+    /// Replaces every existing value of `name`.
+    pub fn insert(&mut self, name: HeaderName, value: HeaderValue) {
+        self.entries.retain(|(key, _)| *key != name);
+        self.entries.push((name, value));
+    }
+
+    pub fn append(&mut self, name: HeaderName, value: HeaderValue) {
+        self.entries.push((name, value));
+    }
+
+    pub fn try_insert(&mut self, name: &str, value: &str) -> Result<(), InvalidHeader> {
+        self.insert(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
+        Ok(())
+    }
+
+    pub fn try_append(&mut self, name: &str, value: &str) -> Result<(), InvalidHeader> {
+        self.append(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
+        Ok(())
+    }
+
+    /// Removes every value of `name`, returning the first one.
+    pub fn remove(&mut self, name: &str) -> Option<HeaderValue> {
+        let mut removed = None;
+        self.entries.retain(|(key, value)| {
+            if key != name {
+                return true;
+            }
+            if removed.is_none() {
+                removed = Some(value.clone());
+            }
+            false
+        });
+        removed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &HeaderValue)> {
+        self.entries.iter().map(|(name, value)| (name, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Extend<(HeaderName, HeaderValue)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (HeaderName, HeaderValue)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a HeaderName, &'a HeaderValue);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (HeaderName, HeaderValue)>,
+        fn(&'a (HeaderName, HeaderValue)) -> (&'a HeaderName, &'a HeaderValue)
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(name, value)| (name, value))
+    }
+}
+
+pub(crate) fn is_token_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+// Parameters after the first `;` of a header such as Content-Type or
+// Content-Disposition. Names are lowercased and quoted values unescaped.
+pub(crate) fn header_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = value.split_once(';').map(|(_, rest)| rest).unwrap_or_default();
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+        let Some((name, after)) = rest.split_once('=') else {
+            break;
+        };
+        let after = after.trim_start();
+
+        let (value, remaining) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut end = quoted.len();
+                let mut chars = quoted.char_indices();
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next().map(|(_, escaped)| escaped)),
+                        '"' => {
+                            end = index + 1;
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                (value, &quoted[end..])
+            }
+            None => {
+                let end = after.find(';').unwrap_or(after.len());
+                (after[..end].trim().to_string(), &after[end..])
+            }
+        };
+
+        params.push((name.trim().to_ascii_lowercase(), value));
+        rest = remaining;
+    }
+
+    params
+}
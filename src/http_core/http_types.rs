@@ -1,6 +1,10 @@
+use std::fmt;
 use std::str::FromStr;
+use serde::{ Deserialize, Serialize };
+use super::headers::{ header_params, is_token_byte };
 
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum HttpMethods {
     GET,
     POST,
@@ -11,10 +15,12 @@ pub enum HttpMethods {
     PATCH,
     CONNECT,
     TRACE,
+    /// Any other syntactically valid method token, e.g. `PROPFIND`.
+    Extension(String),
 }
 
 impl HttpMethods {
-    pub fn to_string(&self) -> &str {
+    pub fn as_str(&self) -> &str {
         match self {
             HttpMethods::GET => "GET",
             HttpMethods::POST => "POST",
@@ -25,10 +31,381 @@ impl HttpMethods {
             HttpMethods::PATCH => "PATCH",
             HttpMethods::CONNECT => "CONNECT",
             HttpMethods::TRACE => "TRACE",
+            HttpMethods::Extension(method) => method,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMethod(pub String);
+
+impl fmt::Display for InvalidMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid method: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMethod {}
+
+impl FromStr for HttpMethods {
+    type Err = InvalidMethod;
+
+    // Methods are case-sensitive, so `get` is an extension method, not GET.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GET" => Ok(HttpMethods::GET),
+            "POST" => Ok(HttpMethods::POST),
+            "PUT" => Ok(HttpMethods::PUT),
+            "DELETE" => Ok(HttpMethods::DELETE),
+            "HEAD" => Ok(HttpMethods::HEAD),
+            "OPTIONS" => Ok(HttpMethods::OPTIONS),
+            "PATCH" => Ok(HttpMethods::PATCH),
+            "CONNECT" => Ok(HttpMethods::CONNECT),
+            "TRACE" => Ok(HttpMethods::TRACE),
+            other if !other.is_empty() && other.bytes().all(is_token_byte) =>
+                Ok(HttpMethods::Extension(other.to_string())),
+            other => Err(InvalidMethod(other.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for HttpMethods {
+    type Error = InvalidMethod;
+
+    fn try_from(method: String) -> Result<Self, Self::Error> {
+        method.parse()
+    }
+}
+
+impl From<HttpMethods> for String {
+    fn from(method: HttpMethods) -> Self {
+        method.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for HttpMethods {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for HttpMethods {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for HttpMethods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// HTTP status code.
+///
+/// Any three-digit code can be represented; codes from the IANA registry
+/// have associated constants and reason phrases.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StatusCode(u16);
+
+macro_rules! status_codes {
+    ($(($code:expr, $name:ident, $reason:expr);)+) => {
+        impl StatusCode {
+            $(pub const $name: StatusCode = StatusCode($code);)+
+
+            pub fn canonical_reason(&self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some($reason),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+status_codes! {
+    (100, CONTINUE, "Continue");
+    (101, SWITCHING_PROTOCOLS, "Switching Protocols");
+    (102, PROCESSING, "Processing");
+    (103, EARLY_HINTS, "Early Hints");
+    (200, OK, "OK");
+    (201, CREATED, "Created");
+    (202, ACCEPTED, "Accepted");
+    (203, NON_AUTHORITATIVE_INFORMATION, "Non-Authoritative Information");
+    (204, NO_CONTENT, "No Content");
+    (205, RESET_CONTENT, "Reset Content");
+    (206, PARTIAL_CONTENT, "Partial Content");
+    (207, MULTI_STATUS, "Multi-Status");
+    (208, ALREADY_REPORTED, "Already Reported");
+    (226, IM_USED, "IM Used");
+    (300, MULTIPLE_CHOICES, "Multiple Choices");
+    (301, MOVED_PERMANENTLY, "Moved Permanently");
+    (302, FOUND, "Found");
+    (303, SEE_OTHER, "See Other");
+    (304, NOT_MODIFIED, "Not Modified");
+    (305, USE_PROXY, "Use Proxy");
+    (307, TEMPORARY_REDIRECT, "Temporary Redirect");
+    (308, PERMANENT_REDIRECT, "Permanent Redirect");
+    (400, BAD_REQUEST, "Bad Request");
+    (401, UNAUTHORIZED, "Unauthorized");
+    (402, PAYMENT_REQUIRED, "Payment Required");
+    (403, FORBIDDEN, "Forbidden");
+    (404, NOT_FOUND, "Not Found");
+    (405, METHOD_NOT_ALLOWED, "Method Not Allowed");
+    (406, NOT_ACCEPTABLE, "Not Acceptable");
+    (407, PROXY_AUTHENTICATION_REQUIRED, "Proxy Authentication Required");
+    (408, REQUEST_TIMEOUT, "Request Timeout");
+    (409, CONFLICT, "Conflict");
+    (410, GONE, "Gone");
+    (411, LENGTH_REQUIRED, "Length Required");
+    (412, PRECONDITION_FAILED, "Precondition Failed");
+    (413, PAYLOAD_TOO_LARGE, "Content Too Large");
+    (414, URI_TOO_LONG, "URI Too Long");
+    (415, UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type");
+    (416, RANGE_NOT_SATISFIABLE, "Range Not Satisfiable");
+    (417, EXPECTATION_FAILED, "Expectation Failed");
+    (421, MISDIRECTED_REQUEST, "Misdirected Request");
+    (422, UNPROCESSABLE_CONTENT, "Unprocessable Content");
+    (423, LOCKED, "Locked");
+    (424, FAILED_DEPENDENCY, "Failed Dependency");
+    (425, TOO_EARLY, "Too Early");
+    (426, UPGRADE_REQUIRED, "Upgrade Required");
+    (428, PRECONDITION_REQUIRED, "Precondition Required");
+    (429, TOO_MANY_REQUESTS, "Too Many Requests");
+    (431, REQUEST_HEADER_FIELDS_TOO_LARGE, "Request Header Fields Too Large");
+    (451, UNAVAILABLE_FOR_LEGAL_REASONS, "Unavailable For Legal Reasons");
+    (500, INTERNAL_SERVER_ERROR, "Internal Server Error");
+    (501, NOT_IMPLEMENTED, "Not Implemented");
+    (502, BAD_GATEWAY, "Bad Gateway");
+    (503, SERVICE_UNAVAILABLE, "Service Unavailable");
+    (504, GATEWAY_TIMEOUT, "Gateway Timeout");
+    (505, HTTP_VERSION_NOT_SUPPORTED, "HTTP Version Not Supported");
+    (506, VARIANT_ALSO_NEGOTIATES, "Variant Also Negotiates");
+    (507, INSUFFICIENT_STORAGE, "Insufficient Storage");
+    (508, LOOP_DETECTED, "Loop Detected");
+    (510, NOT_EXTENDED, "Not Extended");
+    (511, NETWORK_AUTHENTICATION_REQUIRED, "Network Authentication Required");
+}
+
+impl StatusCode {
+    pub fn from_u16(code: u16) -> Result<Self, InvalidStatusCode> {
+        if (100..1000).contains(&code) {
+            Ok(StatusCode(code))
+        } else {
+            Err(InvalidStatusCode(code))
+        }
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidStatusCode(pub u16);
+
+impl fmt::Display for InvalidStatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid status code: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidStatusCode {}
+
+// Infallible so handlers can keep writing `Response::new(299)`; use
+// `StatusCode::from_u16` to validate untrusted input. A code outside
+// 100..=999 is a bug: it panics in debug builds and becomes a 500 otherwise.
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        debug_assert!((100..1000).contains(&code), "Invalid status code: {}", code);
+        StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl From<StatusCode> for u16 {
+    fn from(status: StatusCode) -> Self {
+        status.0
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<StatusCode> for u16 {
+    fn eq(&self, other: &StatusCode) -> bool {
+        *self == other.0
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.0, self.canonical_reason().unwrap_or("Unknown"))
+    }
+}
+
+/// Parsed media type such as `application/vnd.api+json; charset=utf-8`.
+///
+/// Type, subtype and parameter names are lowercased; parameter values keep
+/// their case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMediaType(pub String);
+
+impl fmt::Display for InvalidMediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid media type: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMediaType {}
+
+impl MediaType {
+    pub fn new(type_: &str, subtype: &str) -> Self {
+        Self {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        self.params.retain(|(key, _)| *key != name);
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// Full subtype, including any structured syntax suffix.
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Structured syntax suffix, e.g. `json` for `application/problem+json`.
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype.rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+
+    /// `type/subtype` without parameters.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Compares type and subtype, treating `*` on either side as a wildcard.
+    /// Parameters are ignored.
+    pub fn matches(&self, other: &MediaType) -> bool {
+        let types = self.type_ == "*" || other.type_ == "*" || self.type_ == other.type_;
+        let subtypes = self.subtype == "*" || other.subtype == "*" || self.subtype == other.subtype;
+        types && subtypes
+    }
+
+    pub fn is_json(&self) -> bool {
+        (self.type_ == "application" && self.subtype == "json") || self.suffix() == Some("json")
+    }
+
+    pub fn is_form_urlencoded(&self) -> bool {
+        self.type_ == "application" && self.subtype == "x-www-form-urlencoded"
+    }
+
+    pub fn is_multipart(&self) -> bool {
+        self.type_ == "multipart"
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = InvalidMediaType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let essence = s.split(';').next().unwrap_or_default().trim();
+        let is_token = |part: &str| !part.is_empty() && part.bytes().all(is_token_byte);
+
+        match essence.split_once('/') {
+            Some((type_, subtype)) if is_token(type_) && is_token(subtype) => {
+                let mut media_type = MediaType::new(type_, subtype);
+                media_type.params = header_params(s);
+                Ok(media_type)
+            }
+            _ => Err(InvalidMediaType(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        for (name, value) in &self.params {
+            if !value.is_empty() && value.bytes().all(is_token_byte) {
+                write!(f, "; {}={}", name, value)?;
+            } else {
+                write!(f, "; {}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<ContentType> for MediaType {
+    fn from(content_type: ContentType) -> Self {
+        let media_type = content_type
+            .as_str()
+            .parse()
+            .unwrap_or_else(|_| MediaType::new("application", "octet-stream"));
+        match content_type {
+            ContentType::TextHtml | ContentType::TextPlain => media_type.with_param("charset", "utf-8"),
+            _ => media_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ContentType {
     ApplicationJson,
     TextHtml,
@@ -44,16 +421,76 @@ pub enum ContentType {
     Custom(String),
 }
 
-pub trait ParseFromString: Sized {
-    type Err;
-    fn from_str(s: &str) -> Result<Self, Self::Err>;
+impl ContentType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ContentType::ApplicationJson => "application/json",
+            ContentType::TextHtml => "text/html",
+            ContentType::TextPlain => "text/plain",
+            ContentType::ApplicationXml => "application/xml",
+            ContentType::ApplicationXhtmlXml => "application/xhtml+xml",
+            ContentType::ApplicationJavascript => "application/javascript",
+            ContentType::ApplicationFormUrlencoded => "application/x-www-form-urlencoded",
+            ContentType::MultipartFormData => "multipart/form-data",
+            ContentType::ImagePng => "image/png",
+            ContentType::ImageJpeg => "image/jpeg",
+            ContentType::ImageGif => "image/gif",
+            ContentType::Custom(essence) => essence,
+        }
+    }
+
+    /// Guesses the type of a file from its extension, falling back to
+    /// `application/octet-stream`.
+    pub fn from_extension(extension: &str) -> Self {
+        let custom = |essence: &str| ContentType::Custom(essence.to_string());
+        match extension.to_ascii_lowercase().as_str() {
+            "html" | "htm" => ContentType::TextHtml,
+            "txt" => ContentType::TextPlain,
+            "json" | "map" => ContentType::ApplicationJson,
+            "xml" => ContentType::ApplicationXml,
+            "xhtml" => ContentType::ApplicationXhtmlXml,
+            "js" | "mjs" => ContentType::ApplicationJavascript,
+            "png" => ContentType::ImagePng,
+            "jpg" | "jpeg" => ContentType::ImageJpeg,
+            "gif" => ContentType::ImageGif,
+            "css" => custom("text/css"),
+            "csv" => custom("text/csv"),
+            "md" => custom("text/markdown"),
+            "svg" => custom("image/svg+xml"),
+            "webp" => custom("image/webp"),
+            "ico" => custom("image/x-icon"),
+            "avif" => custom("image/avif"),
+            "wasm" => custom("application/wasm"),
+            "pdf" => custom("application/pdf"),
+            "zip" => custom("application/zip"),
+            "gz" => custom("application/gzip"),
+            "woff" => custom("font/woff"),
+            "woff2" => custom("font/woff2"),
+            "ttf" => custom("font/ttf"),
+            "otf" => custom("font/otf"),
+            "mp3" => custom("audio/mpeg"),
+            "wav" => custom("audio/wav"),
+            "ogg" => custom("audio/ogg"),
+            "mp4" => custom("video/mp4"),
+            "webm" => custom("video/webm"),
+            _ => custom("application/octet-stream"),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl FromStr for ContentType {
-    type Err = ();
+    type Err = InvalidMediaType;
 
+    // Parameters such as `charset` or `boundary` don't change the type.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
+        let essence = s.parse::<MediaType>()?.essence();
+        match essence.as_str() {
             "application/json" => Ok(ContentType::ApplicationJson),
             "text/html" => Ok(ContentType::TextHtml),
             "text/plain" => Ok(ContentType::TextPlain),
@@ -65,7 +502,7 @@ impl FromStr for ContentType {
             "image/png" => Ok(ContentType::ImagePng),
             "image/jpeg" => Ok(ContentType::ImageJpeg),
             "image/gif" => Ok(ContentType::ImageGif),
-            other => Ok(ContentType::Custom(other.to_string())),
+            _ => Ok(ContentType::Custom(essence)),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,12 @@
 pub mod request;
 pub mod response;
-pub mod http_types;
\ No newline at end of file
+pub mod body;
+pub mod body_stream;
+pub mod http_types;
+pub mod headers;
+pub mod extensions;
+pub mod reader;
+pub mod multipart;
+pub mod negotiation;
+pub mod upgrade;
+pub mod sse;
@@ -0,0 +1,421 @@
+use std::path::{ Path, PathBuf };
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use tokio::io::{ AsyncRead, AsyncReadExt, AsyncWriteExt };
+
+use super::headers::{ header_params, HeaderMap };
+use super::http_types::MediaType;
+use super::reader::find_subsequence;
+
+#[derive(Error, Debug)]
+pub enum MultipartError {
+    #[error("Content-Type is not multipart or has no boundary")]
+    MissingBoundaryError,
+    #[error("Multipart body ended before the closing boundary")]
+    UnexpectedEofError,
+    #[error("Malformed part headers: {0}")] MalformedHeadersError(String),
+    #[error("Part headers exceed the size limit of {0} bytes")] PartHeadersTooLargeError(usize),
+    #[error("Part exceeds the size limit of {0} bytes")] PartTooLargeError(usize),
+    #[error("Multipart body exceeds the size limit of {0} bytes")] PayloadTooLargeError(usize),
+    #[error("Multipart body has more than {0} parts")] TooManyPartsError(usize),
+    #[error("Failed to read body: {0}")] BodyReadError(std::io::Error),
+    #[error("Failed to spool part: {0}")] IoError(#[from] std::io::Error),
+}
+
+impl MultipartError {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            MultipartError::MissingBoundaryError => 415,
+            MultipartError::UnexpectedEofError | MultipartError::MalformedHeadersError(_) => 400,
+            MultipartError::PartHeadersTooLargeError(_) => 431,
+            MultipartError::PartTooLargeError(_) |
+            MultipartError::PayloadTooLargeError(_) |
+            MultipartError::TooManyPartsError(_) => 413,
+            MultipartError::BodyReadError(e) if e.kind() == std::io::ErrorKind::TimedOut => 408,
+            MultipartError::BodyReadError(_) => 400,
+            MultipartError::IoError(_) => 500,
+        }
+    }
+}
+
+/// Limits for a multipart body. Install it with `Router::with_state` to
+/// override the defaults used by the `Multipart` extractor.
+#[derive(Debug, Clone)]
+pub struct MultipartConfig {
+    pub max_part_size: usize,
+    pub max_total_size: usize,
+    pub max_parts: usize,
+    pub max_part_header_size: usize,
+    /// File parts larger than this are written to `spool_dir` instead of kept in memory.
+    pub spool_threshold: Option<usize>,
+    pub spool_dir: PathBuf,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self {
+            max_part_size: 1024 * 1024,
+            max_total_size: 2 * 1024 * 1024,
+            max_parts: 64,
+            max_part_header_size: 8 * 1024,
+            spool_threshold: None,
+            spool_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+impl MultipartConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_part_size(mut self, max_part_size: usize) -> Self {
+        self.max_part_size = max_part_size;
+        self
+    }
+
+    pub fn max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size = max_total_size;
+        self
+    }
+
+    pub fn max_parts(mut self, max_parts: usize) -> Self {
+        self.max_parts = max_parts;
+        self
+    }
+
+    pub fn max_part_header_size(mut self, max_part_header_size: usize) -> Self {
+        self.max_part_header_size = max_part_header_size;
+        self
+    }
+
+    pub fn spool_threshold(mut self, spool_threshold: usize) -> Self {
+        self.spool_threshold = Some(spool_threshold);
+        self
+    }
+
+    pub fn spool_dir(mut self, spool_dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = spool_dir.into();
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum PartData {
+    Memory(Vec<u8>),
+    /// Spooled to disk; the file is removed when the part is dropped.
+    File(NamedTempFile),
+}
+
+/// One field or file of a multipart body.
+#[derive(Debug)]
+pub struct Part {
+    pub headers: HeaderMap,
+    name: Option<String>,
+    filename: Option<String>,
+    size: usize,
+    data: PartData,
+}
+
+impl Part {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get_str("Content-Type")
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn data(&self) -> &PartData {
+        &self.data
+    }
+
+    pub async fn bytes(&self) -> std::io::Result<Vec<u8>> {
+        match &self.data {
+            PartData::Memory(bytes) => Ok(bytes.clone()),
+            PartData::File(file) => tokio::fs::read(file.path()).await,
+        }
+    }
+
+    pub async fn text(&self) -> std::io::Result<String> {
+        String::from_utf8(self.bytes().await?).map_err(|e|
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+        )
+    }
+
+    /// Moves the part's contents to `path`, renaming the spooled file when possible.
+    pub async fn save_to(self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        match self.data {
+            PartData::Memory(bytes) => tokio::fs::write(path, bytes).await,
+            PartData::File(file) =>
+                match file.persist(path) {
+                    Ok(_) => Ok(()),
+                    // Renaming fails across filesystems; fall back to copying.
+                    Err(e) => tokio::fs::copy(e.file.path(), path).await.map(|_| ()),
+                }
+        }
+    }
+}
+
+enum Sink {
+    Memory(Vec<u8>),
+    File(NamedTempFile, tokio::fs::File),
+}
+
+/// Incremental `multipart/form-data` parser.
+///
+/// Parts are read from the stream one at a time, so only the current part is
+/// held in memory, and not even that once it is spooled to disk. The server
+/// hands the extractor the connection's `BodyReader` for this, so an upload
+/// is parsed while it arrives.
+pub struct Multipart<R> {
+    stream: R,
+    buffer: Vec<u8>,
+    delimiter: Vec<u8>,
+    config: MultipartConfig,
+    total_size: usize,
+    parts: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> Multipart<R> {
+    pub fn new(stream: R, boundary: &str, config: MultipartConfig) -> Self {
+        Self {
+            stream,
+            // The leading CRLF lets a boundary at the very start match the delimiter.
+            buffer: b"\r\n".to_vec(),
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            config,
+            total_size: 0,
+            parts: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    pub fn config(&self) -> &MultipartConfig {
+        &self.config
+    }
+
+    /// Reads the next part, or `None` after the closing boundary.
+    pub async fn next_part(&mut self) -> Result<Option<Part>, MultipartError> {
+        if self.done {
+            return Ok(None);
+        }
+        if !self.started {
+            self.skip_preamble().await?;
+            self.started = true;
+        }
+
+        // The closing delimiter may be the last bytes of the body, without a CRLF.
+        while self.buffer.len() < 2 {
+            if self.fill_buffer().await? == 0 {
+                return Err(MultipartError::UnexpectedEofError);
+            }
+        }
+        if self.buffer.starts_with(b"--") {
+            self.done = true;
+            self.skip_epilogue().await?;
+            return Ok(None);
+        }
+
+        let line = self.read_line().await?;
+        if !line.trim().is_empty() {
+            return Err(MultipartError::MalformedHeadersError(format!("Unexpected data after boundary: '{}'", line)));
+        }
+
+        self.parts += 1;
+        if self.parts > self.config.max_parts {
+            return Err(MultipartError::TooManyPartsError(self.config.max_parts));
+        }
+
+        let headers = self.read_headers().await?;
+        let disposition = headers.get_str("Content-Disposition").unwrap_or_default();
+        let params = header_params(disposition);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let name = param("name");
+        let filename = param("filename");
+
+        let (size, data) = self.read_body(filename.is_some()).await?;
+        Ok(Some(Part { headers, name, filename, size, data }))
+    }
+
+    async fn skip_preamble(&mut self) -> Result<(), MultipartError> {
+        loop {
+            if let Some(position) = find_subsequence(&self.buffer, &self.delimiter) {
+                self.buffer.drain(..position + self.delimiter.len());
+                return Ok(());
+            }
+            let keep = self.delimiter.len() - 1;
+            if self.buffer.len() > keep {
+                self.buffer.drain(..self.buffer.len() - keep);
+            }
+            if self.fill_buffer().await? == 0 {
+                return Err(MultipartError::UnexpectedEofError);
+            }
+        }
+    }
+
+    /// Discards whatever follows the closing delimiter, so the whole body is
+    /// consumed and the connection can serve its next request.
+    async fn skip_epilogue(&mut self) -> Result<(), MultipartError> {
+        loop {
+            self.total_size += self.buffer.len();
+            self.buffer.clear();
+            if self.total_size > self.config.max_total_size {
+                return Err(MultipartError::PayloadTooLargeError(self.config.max_total_size));
+            }
+            if self.fill_buffer().await? == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn read_headers(&mut self) -> Result<HeaderMap, MultipartError> {
+        let mut headers = HeaderMap::new();
+        let mut size = 0;
+
+        loop {
+            let line = self.read_line().await?;
+            if line.is_empty() {
+                return Ok(headers);
+            }
+
+            size += line.len() + 2;
+            if size > self.config.max_part_header_size {
+                return Err(MultipartError::PartHeadersTooLargeError(self.config.max_part_header_size));
+            }
+
+            line.split_once(':')
+                .and_then(|(name, value)| headers.try_append(name, value).ok())
+                .ok_or_else(|| MultipartError::MalformedHeadersError(line.clone()))?;
+        }
+    }
+
+    async fn read_body(&mut self, is_file: bool) -> Result<(usize, PartData), MultipartError> {
+        let mut sink = Sink::Memory(Vec::new());
+        let mut size = 0;
+
+        loop {
+            if let Some(position) = find_subsequence(&self.buffer, &self.delimiter) {
+                let chunk: Vec<u8> = self.buffer.drain(..position).collect();
+                self.buffer.drain(..self.delimiter.len());
+                self.write(&mut sink, &mut size, &chunk, is_file).await?;
+                break;
+            }
+
+            // Anything but a possible partial delimiter at the end is part data.
+            let safe = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+            if safe > 0 {
+                let chunk: Vec<u8> = self.buffer.drain(..safe).collect();
+                self.write(&mut sink, &mut size, &chunk, is_file).await?;
+            }
+            if self.fill_buffer().await? == 0 {
+                return Err(MultipartError::UnexpectedEofError);
+            }
+        }
+
+        let data = match sink {
+            Sink::Memory(bytes) => PartData::Memory(bytes),
+            Sink::File(temp, mut file) => {
+                file.flush().await?;
+                PartData::File(temp)
+            }
+        };
+        Ok((size, data))
+    }
+
+    async fn write(
+        &mut self,
+        sink: &mut Sink,
+        size: &mut usize,
+        chunk: &[u8],
+        is_file: bool
+    ) -> Result<(), MultipartError> {
+        *size += chunk.len();
+        self.total_size += chunk.len();
+        if *size > self.config.max_part_size {
+            return Err(MultipartError::PartTooLargeError(self.config.max_part_size));
+        }
+        if self.total_size > self.config.max_total_size {
+            return Err(MultipartError::PayloadTooLargeError(self.config.max_total_size));
+        }
+
+        if let Sink::Memory(bytes) = sink {
+            let spool = is_file && self.config.spool_threshold.is_some_and(|threshold| *size > threshold);
+            if spool {
+                let temp = tempfile::Builder
+                    ::new()
+                    .prefix("crabserve-upload-")
+                    .tempfile_in(&self.config.spool_dir)?;
+                let mut file = tokio::fs::File::from_std(temp.reopen()?);
+                file.write_all(bytes).await?;
+                *sink = Sink::File(temp, file);
+            }
+        }
+
+        match sink {
+            Sink::Memory(bytes) => bytes.extend_from_slice(chunk),
+            Sink::File(_, file) => file.write_all(chunk).await?,
+        }
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String, MultipartError> {
+        loop {
+            if let Some(position) = find_subsequence(&self.buffer, b"\r\n") {
+                let line = String::from_utf8_lossy(&self.buffer[..position]).into_owned();
+                self.buffer.drain(..position + 2);
+                return Ok(line);
+            }
+            if self.buffer.len() > self.config.max_part_header_size {
+                return Err(MultipartError::PartHeadersTooLargeError(self.config.max_part_header_size));
+            }
+            if self.fill_buffer().await? == 0 {
+                return Err(MultipartError::UnexpectedEofError);
+            }
+        }
+    }
+
+    async fn fill_buffer(&mut self) -> Result<usize, MultipartError> {
+        let mut chunk = [0; 8192];
+        let n = self.stream.read(&mut chunk).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => MultipartError::UnexpectedEofError,
+            _ => MultipartError::BodyReadError(e),
+        })?;
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(n)
+    }
+}
+
+/// Boundary parameter of a `multipart/*` Content-Type.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let media_type = content_type.parse::<MediaType>().ok()?;
+    if media_type.type_() != "multipart" {
+        return None;
+    }
+
+    media_type
+        .param("boundary")
+        .filter(|value| !value.is_empty() && value.len() <= 70)
+        .map(str::to_string)
+}
@@ -0,0 +1,215 @@
+use serde::Serialize;
+
+use super::headers::{ HeaderMap, HeaderName, HeaderValue };
+use super::http_types::{ ContentType, MediaType, StatusCode };
+use super::request::{ HttpRequest, Request };
+use super::response::Response;
+
+/// One entry of an `Accept*` header together with its q-value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem<T> {
+    pub value: T,
+    pub quality: f32,
+}
+
+/// Entries of a comma-separated `Accept*` header, highest q-value first.
+///
+/// Entries with a malformed q-value are dropped; `q=0` entries are kept
+/// because they explicitly rule a value out.
+pub fn parse_quality_list(headers: &HeaderMap, name: &str) -> Vec<QualityItem<String>> {
+    let mut items: Vec<QualityItem<String>> = headers
+        .get_all(name)
+        .flat_map(|value| value.as_str().split(','))
+        .filter_map(|item| {
+            let mut params = item.split(';');
+            let mut value = params.next().unwrap_or_default().trim().to_string();
+            if value.is_empty() {
+                return None;
+            }
+
+            let mut quality = 1.0;
+            for param in params {
+                match param.trim().split_once('=') {
+                    Some((name, q)) if name.trim().eq_ignore_ascii_case("q") => {
+                        quality = q.trim().parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                        // Anything after q is an accept-extension, not part of the value.
+                        break;
+                    }
+                    _ => {
+                        value.push_str("; ");
+                        value.push_str(param.trim());
+                    }
+                }
+            }
+            Some(QualityItem { value, quality })
+        })
+        .collect();
+
+    items.sort_by(|a, b| b.quality.total_cmp(&a.quality));
+    items
+}
+
+pub fn parse_accept(headers: &HeaderMap) -> Vec<QualityItem<MediaType>> {
+    parse_quality_list(headers, "Accept")
+        .into_iter()
+        .filter_map(|item| {
+            let value = if item.value == "*" { "*/*" } else { &item.value };
+            value
+                .parse()
+                .ok()
+                .map(|value| QualityItem { value, quality: item.quality })
+        })
+        .collect()
+}
+
+/// Index of the available media type the client prefers most.
+///
+/// Each candidate takes the q-value of the most specific range matching it;
+/// ties go to the candidate listed first. Without an `Accept` header the
+/// first candidate wins.
+pub fn negotiate_media_type(
+    accept: &[QualityItem<MediaType>],
+    available: &[MediaType]
+) -> Option<usize> {
+    negotiate(accept, available, |range, candidate| {
+        if !range.matches(candidate) {
+            return None;
+        }
+        if range.type_() == "*" {
+            return Some(0);
+        }
+        if range.subtype() == "*" {
+            return Some(1);
+        }
+        let params_match = range
+            .params()
+            .iter()
+            .all(|(name, value)| {
+                candidate.param(name).is_some_and(|candidate| candidate.eq_ignore_ascii_case(value))
+            });
+        params_match.then_some(2 + range.params().len())
+    })
+}
+
+/// Index of the preferred language tag, using prefix matching so that a
+/// range of `en` accepts `en-GB`.
+pub fn negotiate_language(ranges: &[QualityItem<String>], available: &[&str]) -> Option<usize> {
+    negotiate(ranges, available, |range, tag| {
+        if range == "*" {
+            return Some(0);
+        }
+        let matches =
+            tag.eq_ignore_ascii_case(range) ||
+            (tag.len() > range.len() &&
+                tag.as_bytes()[range.len()] == b'-' &&
+                tag[..range.len()].eq_ignore_ascii_case(range));
+        matches.then_some(range.len())
+    })
+}
+
+pub fn negotiate_charset(ranges: &[QualityItem<String>], available: &[&str]) -> Option<usize> {
+    negotiate(ranges, available, |range, charset| {
+        if range == "*" {
+            Some(0)
+        } else {
+            charset.eq_ignore_ascii_case(range).then_some(1)
+        }
+    })
+}
+
+/// Index of the preferred content coding from `Accept-Encoding`.
+pub fn negotiate_encoding(ranges: &[QualityItem<String>], available: &[&str]) -> Option<usize> {
+    negotiate_charset(ranges, available)
+}
+
+fn negotiate<R, T>(
+    ranges: &[QualityItem<R>],
+    available: &[T],
+    specificity: impl Fn(&R, &T) -> Option<usize>
+) -> Option<usize> {
+    if ranges.is_empty() {
+        return if available.is_empty() { None } else { Some(0) };
+    }
+
+    let mut best: Option<(usize, f32)> = None;
+    for (index, candidate) in available.iter().enumerate() {
+        let quality = ranges
+            .iter()
+            .filter_map(|range| specificity(&range.value, candidate).map(|score| (score, range.quality)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, quality)| quality)
+            .unwrap_or(0.0);
+
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((index, quality));
+        }
+    }
+
+    best.map(|(index, _)| index)
+}
+
+/// Builds a response from whichever registered representation the request's
+/// `Accept` header prefers, or a 406 when none is acceptable.
+pub struct Representations<'a> {
+    request: &'a Request,
+    status_code: StatusCode,
+    representations: Vec<(MediaType, Result<Vec<u8>, String>)>,
+}
+
+impl<'a> Representations<'a> {
+    pub fn new(request: &'a Request) -> Self {
+        Self { request, status_code: StatusCode::OK, representations: Vec::new() }
+    }
+
+    pub fn with_status(mut self, status_code: impl Into<StatusCode>) -> Self {
+        self.status_code = status_code.into();
+        self
+    }
+
+    pub fn add(mut self, content_type: impl Into<MediaType>, body: impl Into<Vec<u8>>) -> Self {
+        self.representations.push((content_type.into(), Ok(body.into())));
+        self
+    }
+
+    pub fn add_json<T: Serialize>(mut self, data: &T) -> Self {
+        let body = serde_json::to_vec(data).map_err(|e| e.to_string());
+        self.representations.push((ContentType::ApplicationJson.into(), body));
+        self
+    }
+
+    pub fn add_html(self, html: impl Into<String>) -> Self {
+        self.add(ContentType::TextHtml, html.into())
+    }
+
+    pub fn add_text(self, text: impl Into<String>) -> Self {
+        self.add(ContentType::TextPlain, text.into())
+    }
+
+    pub fn build(self) -> Response {
+        let available: Vec<MediaType> = self.representations
+            .iter()
+            .map(|(media_type, _)| media_type.clone())
+            .collect();
+
+        let mut response = match negotiate_media_type(&self.request.accept(), &available) {
+            Some(index) =>
+                match &self.representations[index] {
+                    (media_type, Ok(body)) =>
+                        Response::new(self.status_code)
+                            .set_content_type(media_type.clone())
+                            .add_body(body.clone()),
+                    (_, Err(e)) =>
+                        Response::new(500).add_body(format!("Failed to serialize JSON: {}", e).into_bytes()),
+                }
+            None => {
+                let available: Vec<String> = available.iter().map(MediaType::essence).collect();
+                Response::new(406).add_body(
+                    format!("Not Acceptable. Available representations: {}", available.join(", ")).into_bytes()
+                )
+            }
+        };
+
+        response.headers.append(HeaderName::from_static("Vary"), HeaderValue::from_static("Accept"));
+        response
+    }
+}
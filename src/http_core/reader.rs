@@ -0,0 +1,358 @@
+use std::time::Duration;
+use tokio::io::{ AsyncRead, AsyncReadExt };
+use tokio::time::timeout;
+use thiserror::Error;
+
+use crate::config::config::ServerConfig;
+use super::body_stream::{ BodySender, BodyStream };
+use super::headers::{ HeaderMap, HeaderName, HeaderValue };
+use super::multipart::boundary;
+use super::request::{ HttpRequest, Request, RequestError };
+
+#[derive(Error, Debug)]
+pub enum ReadError {
+    #[error("Connection closed before a complete request was received")]
+    ConnectionClosedError,
+    #[error("Failed to read from socket: {0}")] IoError(#[from] std::io::Error),
+    #[error("Request header fields too large")]
+    HeadersTooLargeError,
+    #[error("Payload too large")]
+    PayloadTooLargeError,
+    #[error("Request was not received in time")]
+    TimeoutError,
+    #[error("Handler stopped reading the request body")]
+    BodyAbandonedError,
+    #[error("Bad request: {0}")] BadRequestError(String),
+    #[error("Not implemented: {0}")] NotImplementedError(String),
+    #[error("HTTP version not supported: '{0}'")] VersionNotSupportedError(String),
+}
+
+impl ReadError {
+    /// Status code to answer with, or `None` when the connection is unusable.
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            ReadError::ConnectionClosedError | ReadError::IoError(_) | ReadError::BodyAbandonedError => None,
+            ReadError::HeadersTooLargeError => Some(431),
+            ReadError::PayloadTooLargeError => Some(413),
+            ReadError::TimeoutError => Some(408),
+            ReadError::BadRequestError(_) => Some(400),
+            ReadError::NotImplementedError(_) => Some(501),
+            ReadError::VersionNotSupportedError(_) => Some(505),
+        }
+    }
+}
+
+impl From<RequestError> for ReadError {
+    fn from(error: RequestError) -> Self {
+        match error {
+            RequestError::UnsupportedVersionError(version) => ReadError::VersionNotSupportedError(version),
+            error => ReadError::BadRequestError(error.to_string()),
+        }
+    }
+}
+
+/// Incremental HTTP/1.1 request reader.
+///
+/// Bytes read past the end of one request stay buffered for the next call.
+/// Waiting for a request to start is bounded by the keep-alive timeout;
+/// once it has started, each read must arrive within the read timeout.
+///
+/// `multipart/form-data` bodies are not read with the request. The request
+/// carries a `BodyStream` instead, which `read_streamed_body` feeds while the
+/// handler parses it, so uploads are limited by `MultipartConfig` rather
+/// than `max_body_size`.
+pub struct RequestReader<R> {
+    stream: R,
+    buffer: Vec<u8>,
+    max_header_size: usize,
+    max_body_size: usize,
+    idle_timeout: Duration,
+    read_timeout: Duration,
+    streamed_body: Option<(Framing, BodySender)>,
+}
+
+enum Framing {
+    Length(usize),
+    Chunked,
+}
+
+// Where body bytes go: into the request, or on to a handler reading along.
+enum BodySink<'a> {
+    Buffer(&'a mut Vec<u8>),
+    Channel(&'a BodySender),
+}
+
+impl<R: AsyncRead + Unpin> RequestReader<R> {
+    pub fn new(stream: R, config: &ServerConfig) -> Self {
+        Self {
+            stream,
+            buffer: Vec::with_capacity(1024),
+            max_header_size: config.max_header_size,
+            max_body_size: config.max_body_size,
+            idle_timeout: config.keep_alive_timeout,
+            read_timeout: config.read_timeout,
+            streamed_body: None,
+        }
+    }
+
+    /// The stream, and whatever was read from it past the last request.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        (self.stream, self.buffer)
+    }
+
+    /// Reads the next request, or `None` if the peer closed the connection
+    /// cleanly or sent nothing within the keep-alive timeout.
+    pub async fn read_request(&mut self) -> Result<Option<Request>, ReadError> {
+        let head_end = loop {
+            if let Some(position) = find_subsequence(&self.buffer, b"\r\n\r\n") {
+                if position > self.max_header_size {
+                    return Err(ReadError::HeadersTooLargeError);
+                }
+                break position;
+            }
+            if self.buffer.len() > self.max_header_size {
+                return Err(ReadError::HeadersTooLargeError);
+            }
+            if self.buffer.is_empty() {
+                match self.fill_buffer(self.idle_timeout).await {
+                    Ok(0) | Err(ReadError::TimeoutError) => {
+                        return Ok(None);
+                    }
+                    result => result?,
+                };
+            } else if self.fill_buffer(self.read_timeout).await? == 0 {
+                return Err(ReadError::ConnectionClosedError);
+            }
+        };
+
+        let head = std::str
+            ::from_utf8(&self.buffer[..head_end])
+            .map_err(|_| ReadError::BadRequestError("Request head is not valid UTF-8".to_string()))?
+            .to_string();
+        self.buffer.drain(..head_end + 4);
+
+        let transfer_coding: Vec<String> = header_values(&head, "transfer-encoding")
+            .flat_map(|value| value.split(','))
+            .map(|coding| coding.trim().to_ascii_lowercase())
+            .filter(|coding| !coding.is_empty())
+            .collect();
+
+        let framing = if transfer_coding.is_empty() {
+            Framing::Length(content_length(&head)?)
+        } else {
+            // Accepting both framings would let a proxy and this server
+            // disagree about where the request ends.
+            if header_values(&head, "content-length").next().is_some() {
+                return Err(
+                    ReadError::BadRequestError(
+                        "Both Content-Length and Transfer-Encoding are present".to_string()
+                    )
+                );
+            }
+            if transfer_coding != ["chunked"] {
+                return Err(
+                    ReadError::NotImplementedError(
+                        format!("Transfer-Encoding: {}", transfer_coding.join(", "))
+                    )
+                );
+            }
+            Framing::Chunked
+        };
+
+        if header_values(&head, "content-type").next().and_then(boundary).is_some() {
+            let mut request = Request::from_parts(&head, Vec::new()).await?;
+            // `from_parts` counted the empty body; the real length is still to come.
+            request.headers.remove("Content-Length");
+            if let Framing::Length(length) = framing {
+                request.headers.insert(HeaderName::from_static("Content-Length"), HeaderValue::from(length));
+            }
+            let (sender, body) = BodyStream::channel();
+            request.extensions.insert(body);
+            self.streamed_body = Some((framing, sender));
+            return Ok(Some(request));
+        }
+
+        if let Framing::Length(length) = framing {
+            if length > self.max_body_size {
+                return Err(ReadError::PayloadTooLargeError);
+            }
+        }
+        let mut body = Vec::new();
+        let trailers = self.read_body(framing, &mut BodySink::Buffer(&mut body)).await?;
+
+        let mut request = Request::from_parts(&head, body).await?;
+        request.trailers = trailers;
+
+        Ok(Some(request))
+    }
+
+    /// Whether the last request's body is left for `read_streamed_body`.
+    pub fn has_streamed_body(&self) -> bool {
+        self.streamed_body.is_some()
+    }
+
+    /// Reads the body of the last request, if it was left streaming, and
+    /// hands it to the request's `BodyStream` as it arrives. Failures reach
+    /// the handler as read errors. Unless this completes, the connection is
+    /// somewhere inside the body and can't carry another request.
+    pub async fn read_streamed_body(&mut self) -> Result<(), ReadError> {
+        let Some((framing, sender)) = self.streamed_body.take() else {
+            return Ok(());
+        };
+
+        // Trailers arrive after the handler has its request and are dropped.
+        let result = self.read_body(framing, &mut BodySink::Channel(&sender)).await;
+        if let Err(e) = &result {
+            let kind = match e {
+                ReadError::IoError(e) => e.kind(),
+                ReadError::ConnectionClosedError => std::io::ErrorKind::UnexpectedEof,
+                ReadError::TimeoutError => std::io::ErrorKind::TimedOut,
+                _ => std::io::ErrorKind::InvalidData,
+            };
+            let _ = sender.send(Err(std::io::Error::new(kind, e.to_string()))).await;
+        }
+        result.map(|_| ())
+    }
+
+    async fn read_body(&mut self, framing: Framing, sink: &mut BodySink<'_>) -> Result<HeaderMap, ReadError> {
+        match framing {
+            Framing::Length(length) => {
+                self.forward(length, sink).await?;
+                Ok(HeaderMap::new())
+            }
+            Framing::Chunked => self.read_chunked_body(sink).await,
+        }
+    }
+
+    async fn read_chunked_body(&mut self, sink: &mut BodySink<'_>) -> Result<HeaderMap, ReadError> {
+        loop {
+            let line = self.read_line().await?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = Some(size)
+                .filter(|size| !size.is_empty() && size.bytes().all(|byte| byte.is_ascii_hexdigit()))
+                .and_then(|size| usize::from_str_radix(size, 16).ok())
+                .ok_or_else(|| ReadError::BadRequestError(format!("Invalid chunk size: '{}'", size)))?;
+
+            if size == 0 {
+                break;
+            }
+            if let BodySink::Buffer(body) = sink {
+                if size > self.max_body_size - body.len() {
+                    return Err(ReadError::PayloadTooLargeError);
+                }
+            }
+
+            self.forward(size, sink).await?;
+            self.fill_to(2).await?;
+            if &self.buffer[..2] != b"\r\n" {
+                return Err(ReadError::BadRequestError("Chunk is not terminated by CRLF".to_string()));
+            }
+            self.buffer.drain(..2);
+        }
+
+        let mut trailers = HeaderMap::new();
+        let mut trailers_size = 0;
+
+        loop {
+            let line = self.read_line().await?;
+            if line.is_empty() {
+                break;
+            }
+
+            trailers_size += line.len() + 2;
+            if trailers_size > self.max_header_size {
+                return Err(ReadError::HeadersTooLargeError);
+            }
+
+            line.split_once(':')
+                .and_then(|(name, value)| trailers.try_append(name, value).ok())
+                .ok_or_else(|| ReadError::BadRequestError(format!("Malformed trailer: '{}'", line)))?;
+        }
+
+        Ok(trailers)
+    }
+
+    // Moves `length` body bytes to `sink`, a read at a time.
+    async fn forward(&mut self, mut length: usize, sink: &mut BodySink<'_>) -> Result<(), ReadError> {
+        while length > 0 {
+            if self.buffer.is_empty() && self.fill_buffer(self.read_timeout).await? == 0 {
+                return Err(ReadError::ConnectionClosedError);
+            }
+            let n = length.min(self.buffer.len());
+            let bytes = self.buffer.drain(..n);
+            match sink {
+                BodySink::Buffer(body) => body.extend(bytes),
+                BodySink::Channel(sender) => {
+                    let chunk = bytes.collect();
+                    sender.send(Ok(chunk)).await.map_err(|_| ReadError::BodyAbandonedError)?;
+                }
+            }
+            length -= n;
+        }
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String, ReadError> {
+        loop {
+            if let Some(position) = find_subsequence(&self.buffer, b"\r\n") {
+                let line = String::from_utf8(self.buffer[..position].to_vec()).map_err(|_|
+                    ReadError::BadRequestError("Line is not valid UTF-8".to_string())
+                )?;
+                self.buffer.drain(..position + 2);
+                return Ok(line);
+            }
+            if self.buffer.len() > self.max_header_size {
+                return Err(ReadError::HeadersTooLargeError);
+            }
+            if self.fill_buffer(self.read_timeout).await? == 0 {
+                return Err(ReadError::ConnectionClosedError);
+            }
+        }
+    }
+
+    async fn fill_to(&mut self, length: usize) -> Result<(), ReadError> {
+        while self.buffer.len() < length {
+            if self.fill_buffer(self.read_timeout).await? == 0 {
+                return Err(ReadError::ConnectionClosedError);
+            }
+        }
+        Ok(())
+    }
+
+    async fn fill_buffer(&mut self, limit: Duration) -> Result<usize, ReadError> {
+        let mut chunk = [0; 4096];
+        let n = timeout(limit, self.stream.read(&mut chunk)).await.map_err(|_| ReadError::TimeoutError)??;
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(n)
+    }
+}
+
+fn content_length(head: &str) -> Result<usize, ReadError> {
+    let mut length = None;
+
+    // `parse` would also take a leading `+`, which other servers may frame differently.
+    for value in header_values(head, "content-length") {
+        let parsed = Some(value)
+            .filter(|value| !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|value| value.parse::<usize>().ok())
+            .ok_or_else(|| ReadError::BadRequestError(format!("Invalid Content-Length: '{}'", value)))?;
+        if length.is_some_and(|length| length != parsed) {
+            return Err(ReadError::BadRequestError("Conflicting Content-Length headers".to_string()));
+        }
+        length = Some(parsed);
+    }
+
+    Ok(length.unwrap_or(0))
+}
+
+fn header_values<'a>(head: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(move |(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+pub(crate) fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
@@ -1,16 +1,41 @@
 use async_trait::async_trait;
-use std::{ collections::HashMap, str::FromStr };
+use std::collections::HashMap;
 use serde::{ Deserialize, Serialize };
-use serde_json::Value;
-use super::http_types::ContentType;
+use percent_encoding::percent_decode_str;
+use super::http_types::{ HttpMethods, MediaType };
+use super::extensions::Extensions;
+use super::headers::{ HeaderMap, HeaderName, HeaderValue };
+use super::negotiation::{
+    negotiate_charset,
+    negotiate_language,
+    negotiate_media_type,
+    parse_accept,
+    parse_quality_list,
+    QualityItem,
+};
 use thiserror::Error;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Request {
-    pub method: String,
+    pub method: HttpMethods,
+    /// The percent-decoded path.
     pub path: String,
-    pub headers: HashMap<String, String>,
+    /// The path as sent, which routing splits into segments before decoding,
+    /// so an encoded `/` stays inside its segment.
+    #[serde(default)]
+    pub raw_path: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub version: String,
+    pub headers: HeaderMap,
     pub body: Vec<u8>,
+    #[serde(default)]
+    pub trailers: HeaderMap,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(skip)]
+    pub extensions: Extensions,
 }
 
 #[derive(Error, Debug)]
@@ -19,129 +44,163 @@ pub enum RequestError {
     RequestLineParseError,
     #[error("Method not found in request")]
     MethodNotFoundError,
+    #[error("Invalid method: '{0}'")] InvalidMethodError(String),
     #[error("Path not found in request")]
     PathNotFoundError,
+    #[error("Invalid percent-encoding in path: '{0}'")] PathDecodeError(String),
+    #[error("Invalid HTTP version: '{0}'")] InvalidVersionError(String),
+    #[error("Unsupported HTTP version: '{0}'")] UnsupportedVersionError(String),
     #[error("Headers/Body delimiter not found")]
     HeadersBodyDelimiterNotFoundError,
     #[error("Malformed header line: '{0}'")] MalformedHeaderError(String),
     #[error("Failed to parse Content-Type: '{0}'")] ContentTypeParseError(String),
     #[error("Unsupported content type")]
     UnsupportedContentTypeError,
-    #[error("Failed to process JSON body: {0}")] JsonBodyProcessingError(serde_json::Error),
 }
 
 #[async_trait]
 pub trait HttpRequest {
     fn new(method: &str, path: &str) -> Self;
 
-    fn set_headers(&mut self, headers: HashMap<String, String>);
-    fn get_headers(&self) -> &HashMap<String, String>;
+    fn set_headers(&mut self, headers: HeaderMap);
+    fn get_headers(&self) -> &HeaderMap;
     fn set_body(&mut self, body: Vec<u8>);
     fn get_body(&self) -> &[u8];
+    fn get_trailers(&self) -> &HeaderMap;
 
+    /// Replaces any existing values of `key`. Panics if the name or value is
+    /// invalid; use `HeaderMap::try_insert` for untrusted input.
     fn add_header(&mut self, key: &str, value: &str);
 
     fn add_body(&mut self, body: Vec<u8>) {
         self.set_body(body);
     }
 
-    fn method(&self) -> &str;
+    fn method(&self) -> &HttpMethods;
+    /// Parsed `Content-Type`, or `None` when it is missing or malformed.
+    fn content_type(&self) -> Option<MediaType>;
     fn path(&self) -> &str;
+    fn raw_path(&self) -> &str;
+
+    fn accept(&self) -> Vec<QualityItem<MediaType>>;
+    fn accept_language(&self) -> Vec<QualityItem<String>>;
+    fn accept_charset(&self) -> Vec<QualityItem<String>>;
+    /// The entry of `available` the client prefers, or `None` if it accepts none of them.
+    fn preferred_media_type<'a>(&self, available: &'a [MediaType]) -> Option<&'a MediaType>;
+    fn preferred_language<'a>(&self, available: &[&'a str]) -> Option<&'a str>;
+    fn preferred_charset<'a>(&self, available: &[&'a str]) -> Option<&'a str>;
+    fn query(&self) -> &str;
+    fn query_params(&self) -> HashMap<String, String>;
+    fn version(&self) -> &str;
+    fn params(&self) -> &HashMap<String, String>;
+    fn param(&self, name: &str) -> Option<&str>;
 
     async fn parse(raw_req: &str) -> Result<Self, RequestError> where Self: Sized;
-    async fn process_json_body(json_body: &str) -> Result<Value, serde_json::Error>;
-    fn set_body_and_content_length(headers: &mut HashMap<String, String>, body: &[u8]);
+    async fn from_parts(head: &str, body: Vec<u8>) -> Result<Self, RequestError> where Self: Sized;
+    fn set_body_and_content_length(headers: &mut HeaderMap, body: &[u8]);
 }
 
 #[async_trait]
 impl HttpRequest for Request {
     fn new(method: &str, path: &str) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+
         Self {
-            method: method.to_string(),
+            method: method
+                .parse()
+                .unwrap_or_else(|_| HttpMethods::Extension(method.to_string())),
             path: path.to_string(),
-            headers: HashMap::new(),
+            raw_path: path.to_string(),
+            query: query.to_string(),
+            version: "HTTP/1.1".to_string(),
+            headers: HeaderMap::new(),
             body: Vec::new(),
+            trailers: HeaderMap::new(),
+            params: HashMap::new(),
+            extensions: Extensions::new(),
         }
     }
 
-    async fn process_json_body(json_body: &str) -> Result<Value, serde_json::Error> {
-        println!("Row JSON Bosy: {:?}", json_body);
-
-        let trimmed_body: &str = json_body.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    fn set_body_and_content_length(headers: &mut HeaderMap, body: &[u8]) {
+        headers.insert(HeaderName::from_static("Content-Length"), HeaderValue::from(body.len()));
+    }
 
-        let result = serde_json::from_str(trimmed_body);
+    async fn parse(raw_req: &str) -> Result<Self, RequestError> where Self: Sized {
+        let (head, body) = raw_req
+            .split_once("\r\n\r\n")
+            .ok_or(RequestError::HeadersBodyDelimiterNotFoundError)?;
 
-        result
+        Self::from_parts(head, body.as_bytes().to_vec()).await
     }
 
-    fn set_body_and_content_length(headers: &mut HashMap<String, String>, body: &[u8]) {
-        headers.insert("Content-Length".to_string(), body.len().to_string());
-    }
+    async fn from_parts(head: &str, body: Vec<u8>) -> Result<Self, RequestError> where Self: Sized {
+        let mut lines = head.lines();
 
-    async fn parse(raw_req: &str) -> Result<Self, RequestError> where Self: Sized {
-        let result = (async {
-            let mut lines = raw_req.lines();
-
-            let request_line = lines.next().ok_or(RequestError::RequestLineParseError)?;
-            let mut parts = request_line.split_whitespace();
-            let method = parts.next().ok_or(RequestError::MethodNotFoundError)?;
-            let path = parts.next().ok_or(RequestError::PathNotFoundError)?;
-
-            let (headers, body) = raw_req
-                .split_once("\r\n\r\n")
-                .ok_or(RequestError::HeadersBodyDelimiterNotFoundError)?;
-
-            let mut headers_map: HashMap<String, String> = headers
-                .lines()
-                .skip(1)
-                .filter_map(|line| {
-                    let mut parts = line.splitn(2, ':');
-                    match (parts.next(), parts.next()) {
-                        (Some(key), Some(value)) =>
-                            Some((key.trim().to_string(), value.trim().to_string())),
-                        _ => None,
-                    }
-                })
-                .collect();
-
-            let binding = String::from("");
-            let content_type_str = headers_map.get("Content-Type").unwrap_or(&binding);
-            let content_type = ContentType::from_str(&content_type_str).unwrap();
-            let body_bytes = match content_type {
-                ContentType::ApplicationJson => {
-                    let json_value: serde_json::Value = Self::process_json_body(body).await.map_err(
-                        |e| RequestError::JsonBodyProcessingError(e)
-                    )?;
-                    serde_json
-                        ::to_vec(&json_value)
-                        .map_err(|e| RequestError::JsonBodyProcessingError(e))?
-                }
-
-                _ => body.as_bytes().to_vec(),
-            };
-
-            Self::set_body_and_content_length(&mut headers_map, &body_bytes);
-
-            Ok(Self {
-                method: method.to_string(),
-                path: path.to_string(),
-                headers: headers_map,
-                body: body_bytes,
+        let request_line = lines.next().ok_or(RequestError::RequestLineParseError)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().ok_or(RequestError::MethodNotFoundError)?;
+        let method = method
+            .parse::<HttpMethods>()
+            .map_err(|_| RequestError::InvalidMethodError(method.to_string()))?;
+        let target = parts.next().ok_or(RequestError::PathNotFoundError)?;
+        let (raw_path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = percent_decode_str(raw_path)
+            .decode_utf8()
+            .map_err(|_| RequestError::PathDecodeError(raw_path.to_string()))?;
+        let version = parts.next().ok_or(RequestError::RequestLineParseError)?;
+        if parts.next().is_some() {
+            return Err(RequestError::RequestLineParseError);
+        }
+        let (major, minor) = version
+            .strip_prefix("HTTP/")
+            .and_then(|number| number.split_once('.'))
+            .filter(|(major, minor)| {
+                [major, minor].iter().all(|digit| digit.len() == 1 && digit.as_bytes()[0].is_ascii_digit())
             })
-        }).await;
+            .ok_or_else(|| RequestError::InvalidVersionError(version.to_string()))?;
+        if major != "1" || !matches!(minor, "0" | "1") {
+            return Err(RequestError::UnsupportedVersionError(version.to_string()));
+        }
+
+        let mut headers_map = HeaderMap::new();
+        for line in lines {
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| RequestError::MalformedHeaderError(line.to_string()))?;
+            headers_map
+                .try_append(key, value)
+                .map_err(|_| RequestError::MalformedHeaderError(line.to_string()))?;
+        }
 
-        result
+        // The body is kept exactly as received; extractors such as
+        // `Json<T>` interpret it on demand.
+        Self::set_body_and_content_length(&mut headers_map, &body);
+
+        Ok(Self {
+            method,
+            path: path.into_owned(),
+            raw_path: raw_path.to_string(),
+            query: query.to_string(),
+            version: version.to_string(),
+            headers: headers_map,
+            body,
+            trailers: HeaderMap::new(),
+            params: HashMap::new(),
+            extensions: Extensions::new(),
+        })
     }
 
     fn add_header(&mut self, key: &str, value: &str) {
-        self.headers.insert(key.to_string(), value.to_string());
+        if let Err(e) = self.headers.try_insert(key, value) {
+            panic!("{}", e);
+        }
     }
 
-    fn set_headers(&mut self, headers: HashMap<String, String>) {
+    fn set_headers(&mut self, headers: HeaderMap) {
         self.headers = headers;
     }
 
-    fn get_headers(&self) -> &HashMap<String, String> {
+    fn get_headers(&self) -> &HeaderMap {
         &self.headers
     }
 
@@ -153,11 +212,71 @@ impl HttpRequest for Request {
         &self.body
     }
 
-    fn method(&self) -> &str {
+    fn get_trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    fn method(&self) -> &HttpMethods {
         &self.method
     }
 
+    fn content_type(&self) -> Option<MediaType> {
+        self.headers.get_str("Content-Type")?.parse().ok()
+    }
+
     fn path(&self) -> &str {
         &self.path
     }
+
+    // Requests deserialized without a raw path route on the decoded one.
+    fn raw_path(&self) -> &str {
+        if self.raw_path.is_empty() { &self.path } else { &self.raw_path }
+    }
+
+    fn accept(&self) -> Vec<QualityItem<MediaType>> {
+        parse_accept(&self.headers)
+    }
+
+    fn accept_language(&self) -> Vec<QualityItem<String>> {
+        parse_quality_list(&self.headers, "Accept-Language")
+    }
+
+    fn accept_charset(&self) -> Vec<QualityItem<String>> {
+        parse_quality_list(&self.headers, "Accept-Charset")
+    }
+
+    fn preferred_media_type<'a>(&self, available: &'a [MediaType]) -> Option<&'a MediaType> {
+        negotiate_media_type(&self.accept(), available).map(|index| &available[index])
+    }
+
+    fn preferred_language<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        negotiate_language(&self.accept_language(), available).map(|index| available[index])
+    }
+
+    fn preferred_charset<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        negotiate_charset(&self.accept_charset(), available).map(|index| available[index])
+    }
+
+    fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Percent-decoded query parameters; for repeated keys the last value wins.
+    fn query_params(&self) -> HashMap<String, String> {
+        serde_urlencoded::from_str::<Vec<(String, String)>>(&self.query)
+            .map(|pairs| pairs.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn params(&self) -> &HashMap<String, String> {
+        &self.params
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
 }
@@ -1,78 +1,244 @@
-use std::collections::HashMap;
+use std::future::Future;
+use std::time::SystemTime;
+use futures_util::stream::Stream;
 use serde::Serialize;
+use super::body::{ Body, BoxError, FileBody };
+use super::headers::{ HeaderMap, HeaderName, HeaderValue, InvalidHeader };
+use super::http_types::{ MediaType, StatusCode };
+use super::negotiation::Representations;
+use super::request::Request;
+use super::upgrade::{ OnUpgrade, Upgraded };
+use tokio::io::{ AsyncWrite, AsyncWriteExt };
+
+pub const SERVER_NAME: &str = "CrabServer";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Response {
-    pub status_code: u16,
-    pub headers: HashMap<String, String>,
-    pub body: Vec<u8>,
+    pub status_code: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Body,
+    #[serde(skip)]
+    upgrade: Option<OnUpgrade>,
 }
 
 impl Response {
-    pub fn new(status_code: u16) -> Self {
+    pub fn new(status_code: impl Into<StatusCode>) -> Self {
         Self {
-            status_code,
-            headers: HashMap::new(),
-            body: Vec::new(),
+            status_code: status_code.into(),
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+            upgrade: None,
         }
     }
 
+    /// Starts a response whose body is chosen by content negotiation.
+    pub fn negotiate(request: &Request) -> Representations<'_> {
+        Representations::new(request)
+    }
+
     pub fn set_json_body<T: Serialize>(mut self, data: &T) -> serde_json::Result<Self> {
         let serialized = serde_json::to_string(data)?;
-        self.headers.insert("Content-Type".to_string(), "application/json".to_string());
-        self.body = serialized.into();
+        self.headers.insert(
+            HeaderName::from_static("Content-Type"),
+            HeaderValue::from_static("application/json")
+        );
+        self.body = Body::from(serialized);
         Ok(self)
     }
 
-    pub fn format(&self) -> Result<Vec<u8>, std::string::FromUtf8Error> {
-        let status_line = format!(
-            "HTTP/1.1 {} {}\r\n",
-            self.status_code,
-            self.get_status_message()
-        );
+    /// The whole response in memory. Fails for streamed bodies and for file
+    /// bodies that can't be read in full, which `write_to` sends instead.
+    pub fn format(&self) -> std::io::Result<Vec<u8>> {
+        let mut formatted = self.format_head();
+        if !self.allows_body() {
+            return Ok(formatted);
+        }
+
+        let body = self.body.to_vec()?;
+        if self.is_chunked() {
+            formatted.extend_from_slice(&encode_chunk(&body));
+            formatted.extend_from_slice(LAST_CHUNK);
+        } else {
+            formatted.extend_from_slice(&body);
+        }
 
-        let headers = self.headers
-            .iter()
-            .map(|(key, value)| format!("{}: {}\r\n", key, value))
-            .collect::<String>();
+        Ok(formatted)
+    }
+
+    /// Writes only the status line and headers, as the answer to a HEAD request.
+    pub async fn write_head_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.format_head()).await?;
+        writer.flush().await
+    }
 
-        let body = String::from_utf8(self.body.clone())?;
+    pub async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.format_head()).await?;
 
-        Ok(format!("{}{}\r\n{}", status_line, headers, body).into_bytes())
+        // 1xx, 204 and 304 end with the head; body bytes would be read as
+        // the start of the next response.
+        if self.allows_body() {
+            // A streamed body must match the length its handler announced.
+            let length = self.headers.get_str("Content-Length").and_then(|length| length.trim().parse().ok());
+            self.body.write_to(writer, self.is_chunked(), length).await?;
+        }
+        writer.flush().await
     }
 
-    fn get_status_message(&self) -> &str {
-        match self.status_code {
-            200 => "OK",
-            201 => "Created",
-            400 => "Bad Request",
-            401 => "Unauthorized",
-            403 => "Forbidden",
-            404 => "Not Found",
-            500 => "Internal Server Error",
-            _ => "Unknown",
+    /// Status line and headers, including the `Content-Length`, `Date` and
+    /// `Server` headers when the handler did not set them.
+    fn format_head(&self) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {}\r\n", self.status_code);
+
+        for (key, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        for (key, value) in self.implicit_headers() {
+            head.push_str(&format!("{}: {}\r\n", key, value));
         }
+
+        head.push_str("\r\n");
+        head.into_bytes()
     }
 
-    pub fn add_header(mut self, key: &str, value: &str) -> Self {
-        self.headers.insert(key.to_string(), value.to_string());
+    /// The `Content-Length`, `Date` and `Server` headers sent alongside
+    /// `headers` when the handler did not set them.
+    pub(crate) fn implicit_headers(&self) -> Vec<(&'static str, String)> {
+        let mut implicit = Vec::new();
+        if !self.is_chunked() && !self.body.is_stream() && self.allows_body() && !self.has_header("Content-Length") {
+            implicit.push(("Content-Length", self.content_length().to_string()));
+        }
+        if !self.has_header("Date") {
+            implicit.push(("Date", httpdate::fmt_http_date(SystemTime::now())));
+        }
+        if !self.has_header("Server") {
+            implicit.push(("Server", SERVER_NAME.to_string()));
+        }
+        implicit
+    }
+
+    pub(crate) fn allows_body(&self) -> bool {
+        !(self.status_code.is_informational() || self.status_code == 204 || self.status_code == 304)
+    }
+
+    pub fn has_header(&self, name: &str) -> bool {
+        self.headers.contains_key(name)
+    }
+
+    /// Replaces any existing values of `key`. Panics if the name or value is
+    /// invalid; use `try_add_header` for values derived from user input.
+    pub fn add_header(self, key: &str, value: &str) -> Self {
+        match self.try_add_header(key, value) {
+            Ok(response) => response,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_add_header(mut self, key: &str, value: &str) -> Result<Self, InvalidHeader> {
+        self.headers.try_insert(key, value)?;
+        Ok(self)
+    }
+
+    /// Adds a value without replacing existing ones, e.g. for `Set-Cookie`.
+    pub fn append_header(mut self, key: &str, value: &str) -> Self {
+        if let Err(e) = self.headers.try_append(key, value) {
+            panic!("{}", e);
+        }
+        self
+    }
+
+    /// Sets `Content-Type` from a `MediaType` or a `ContentType`.
+    pub fn set_content_type(mut self, content_type: impl Into<MediaType>) -> Self {
+        let value = HeaderValue::try_from(content_type.into().to_string())
+            .expect("a displayed media type is a valid header value");
+        self.headers.insert(HeaderName::from_static("Content-Type"), value);
         self
     }
 
     pub fn add_body(mut self, body: Vec<u8>) -> Self {
-        self.body = body;
+        self.body = Body::Bytes(body);
+        self
+    }
+
+    /// Sends `length` bytes of `file` starting at `offset` as the body,
+    /// streaming them to the socket in chunks.
+    pub fn add_file_body(mut self, file: std::fs::File, offset: u64, length: u64) -> Self {
+        self.body = Body::file(file, offset, length);
+        self
+    }
+
+    /// Sends each chunk of `chunks` as soon as it is produced. Over HTTP/1.1
+    /// the body is chunked unless a `Content-Length` is set; HTTP/1.0
+    /// clients see the end of the body when the connection closes.
+    pub fn add_stream_body<S, E>(mut self, chunks: S) -> Self
+        where S: Stream<Item = Result<Vec<u8>, E>> + Send + 'static, E: Into<BoxError>
+    {
+        self.body = Body::stream(chunks);
+        self
+    }
+
+    pub fn file_body(&self) -> Option<&FileBody> {
+        match &self.body {
+            Body::File(file) => Some(file),
+            _ => None,
+        }
+    }
+
+    /// Hands the connection to `callback` once this response has been sent.
+    /// Only honoured for `101 Switching Protocols` over HTTP/1.1.
+    pub fn on_upgrade<F, Fut>(mut self, callback: F) -> Self
+        where F: FnOnce(Upgraded) -> Fut + Send + 'static, Fut: Future<Output = ()> + Send + 'static
+    {
+        self.upgrade = Some(OnUpgrade::new(callback));
         self
     }
 
-    pub fn status_code(&self) -> u16 {
+    pub fn upgrade(&self) -> Option<&OnUpgrade> {
+        self.upgrade.as_ref()
+    }
+
+    /// The body's length, or 0 for a streamed body.
+    pub fn content_length(&self) -> u64 {
+        self.body.len().unwrap_or(0)
+    }
+
+    pub fn chunked(mut self) -> Self {
+        self.headers.remove("Content-Length");
+        self.headers.insert(
+            HeaderName::from_static("Transfer-Encoding"),
+            HeaderValue::from_static("chunked")
+        );
+        self
+    }
+
+    pub fn is_chunked(&self) -> bool {
+        self.headers.contains_token("Transfer-Encoding", "chunked")
+    }
+
+    pub fn status_code(&self) -> StatusCode {
         self.status_code
     }
 
-    pub fn headers(&self) -> &HashMap<String, String> {
+    pub fn headers(&self) -> &HeaderMap {
         &self.headers
     }
 
+    /// The in-memory body; empty for file and streamed bodies.
     pub fn body(&self) -> &[u8] {
-        &self.body
+        self.body.as_bytes().unwrap_or_default()
     }
 }
+
+pub const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
+
+/// Frames `data` as a single chunk; an empty slice yields no bytes, since a
+/// zero-sized chunk would terminate the body.
+pub fn encode_chunk(data: &[u8]) -> Vec<u8> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let mut chunk = format!("{:x}\r\n", data.len()).into_bytes();
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(b"\r\n");
+    chunk
+}
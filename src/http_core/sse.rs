@@ -0,0 +1,176 @@
+use std::convert::Infallible;
+use std::time::Duration;
+use async_trait::async_trait;
+use futures_util::stream::{ self, Stream, StreamExt };
+use serde::Serialize;
+
+use super::request::Request;
+use super::response::Response;
+use crate::router::handler::{ FromRequest, IntoResponse };
+
+/// One message of a `text/event-stream`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    comment: Option<String>,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+    data: Option<String>,
+}
+
+impl Event {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The payload. Multi-line data is sent as one `data` field per line and
+    /// arrives with `\n` line endings.
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    pub fn json_data<T: Serialize>(self, data: &T) -> serde_json::Result<Self> {
+        Ok(self.data(serde_json::to_string(data)?))
+    }
+
+    /// The event type clients listen for; without one it is `message`.
+    /// Panics if it contains a line break.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        let event = event.into();
+        assert!(!event.contains(['\r', '\n']), "SSE event names must not contain line breaks");
+        self.event = Some(event);
+        self
+    }
+
+    /// Sent back by a reconnecting client as `Last-Event-ID`. Panics if it
+    /// contains a line break or NUL.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        let id = id.into();
+        assert!(!id.contains(['\r', '\n', '\0']), "SSE event ids must not contain line breaks or NUL");
+        self.id = Some(id);
+        self
+    }
+
+    /// How long the client waits before reconnecting after a drop.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// A line clients ignore.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// The event as it goes out on the wire, ending in a blank line.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut formatted = String::new();
+        if let Some(comment) = &self.comment {
+            for line in lines(comment) {
+                formatted.push_str(&format!(": {}\n", line));
+            }
+        }
+        if let Some(event) = &self.event {
+            formatted.push_str(&format!("event: {}\n", event));
+        }
+        if let Some(id) = &self.id {
+            formatted.push_str(&format!("id: {}\n", id));
+        }
+        if let Some(retry) = self.retry {
+            formatted.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        if let Some(data) = &self.data {
+            for line in lines(data) {
+                formatted.push_str(&format!("data: {}\n", line));
+            }
+        }
+        formatted.push('\n');
+        formatted.into_bytes()
+    }
+}
+
+// Event streams accept CRLF, CR and LF as line endings alike.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+    std::iter::from_fn(move || {
+        let text = rest?;
+        match text.find(['\r', '\n']) {
+            Some(end) => {
+                let next = if text[end..].starts_with("\r\n") { end + 2 } else { end + 1 };
+                rest = Some(&text[next..]);
+                Some(&text[..end])
+            }
+            None => {
+                rest = None;
+                Some(text)
+            }
+        }
+    })
+}
+
+/// A `text/event-stream` response that sends each event of `events` as
+/// soon as it is produced.
+///
+/// When no event has gone out for the keep-alive interval (15 seconds by
+/// default) a comment is sent, so proxies don't close the idle connection.
+pub struct Sse<S> {
+    events: S,
+    keep_alive: Option<Duration>,
+}
+
+impl<S: Stream<Item = Event> + Send + 'static> Sse<S> {
+    pub fn new(events: S) -> Self {
+        Self { events, keep_alive: Some(Duration::from_secs(15)) }
+    }
+
+    pub fn keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    pub fn without_keep_alive(mut self) -> Self {
+        self.keep_alive = None;
+        self
+    }
+}
+
+impl<S: Stream<Item = Event> + Send + 'static> IntoResponse for Sse<S> {
+    fn into_response(self) -> Response {
+        let keep_alive = self.keep_alive;
+        let chunks = stream::unfold(self.events.boxed(), move |mut events| async move {
+            let event = match keep_alive {
+                Some(interval) =>
+                    match tokio::time::timeout(interval, events.next()).await {
+                        Ok(event) => event?,
+                        Err(_) => Event::new().comment("keep-alive"),
+                    }
+                None => events.next().await?,
+            };
+            Some((Ok::<_, Infallible>(event.to_bytes()), events))
+        });
+
+        Response::new(200)
+            .add_header("Content-Type", "text/event-stream")
+            .add_header("Cache-Control", "no-cache")
+            .chunked()
+            .add_stream_body(chunks)
+    }
+}
+
+/// The id of the last event a reconnecting client received, from its
+/// `Last-Event-ID` header, so the handler can resume after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastEventId(pub Option<String>);
+
+#[async_trait]
+impl FromRequest for LastEventId {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        let id = request.headers
+            .get_str("Last-Event-ID")
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+        Ok(LastEventId(id))
+    }
+}
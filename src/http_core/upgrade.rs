@@ -0,0 +1,120 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{ Arc, Mutex };
+use std::task::{ Context, Poll };
+use tokio::io::{ AsyncRead, AsyncWrite, ReadBuf };
+
+use crate::router::handler::BoxFuture;
+
+/// A stream that first replays bytes already read from it.
+pub(crate) struct Rewind<S> {
+    prefix: Vec<u8>,
+    position: usize,
+    inner: S,
+}
+
+impl<S> Rewind<S> {
+    pub(crate) fn new(prefix: Vec<u8>, inner: S) -> Self {
+        Self { prefix, position: 0, inner }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Rewind<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        if self.position < self.prefix.len() {
+            let n = buf.remaining().min(self.prefix.len() - self.position);
+            buf.put_slice(&self.prefix[self.position..self.position + n]);
+            self.position += n;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Rewind<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// The connection handed over after a `101 Switching Protocols` response,
+/// plain TCP or TLS alike.
+pub struct Upgraded {
+    io: Rewind<Box<dyn Io>>,
+}
+
+impl Upgraded {
+    /// `buffered` holds bytes the client sent after its request that were
+    /// already read from `io`.
+    pub(crate) fn new<S: AsyncRead + AsyncWrite + Send + Unpin + 'static>(buffered: Vec<u8>, io: S) -> Self {
+        Self { io: Rewind::new(buffered, Box::new(io)) }
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgraded").finish_non_exhaustive()
+    }
+}
+
+type UpgradeCallback = Box<dyn FnOnce(Upgraded) -> BoxFuture<'static, ()> + Send>;
+
+/// What takes over the connection once the response has been sent. Clones
+/// share the callback, which runs at most once.
+#[derive(Clone)]
+pub struct OnUpgrade(Arc<Mutex<Option<UpgradeCallback>>>);
+
+impl OnUpgrade {
+    pub fn new<F, Fut>(callback: F) -> Self
+        where F: FnOnce(Upgraded) -> Fut + Send + 'static, Fut: Future<Output = ()> + Send + 'static
+    {
+        let callback: UpgradeCallback = Box::new(move |upgraded| Box::pin(callback(upgraded)));
+        Self(Arc::new(Mutex::new(Some(callback))))
+    }
+
+    pub(crate) async fn run(&self, upgraded: Upgraded) {
+        let callback = self.0.lock().unwrap().take();
+        if let Some(callback) = callback {
+            callback(upgraded).await;
+        }
+    }
+}
+
+impl fmt::Debug for OnUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnUpgrade").finish()
+    }
+}
@@ -1,7 +1,12 @@
-#[allow(non_snake_case)]
+#![allow(non_snake_case)]
 pub mod http_core;
 pub mod server;
 pub mod router;
 pub mod database;
 pub mod utils;
 pub mod config;
+pub mod tls;
+pub mod http2;
+pub mod websocket;
+
+pub use macros::{ delete, get, head, options, patch, post, put };
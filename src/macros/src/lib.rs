@@ -6,5 +6,35 @@ mod route;
 
 #[proc_macro_attribute]
 pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
-    route::get(args, input)
-}
\ No newline at end of file
+    route::route("GET", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn post(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("POST", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn put(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("PUT", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn delete(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("DELETE", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("PATCH", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn head(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("HEAD", args, input)
+}
+
+#[proc_macro_attribute]
+pub fn options(args: TokenStream, input: TokenStream) -> TokenStream {
+    route::route("OPTIONS", args, input)
+}
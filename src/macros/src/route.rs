@@ -1,19 +1,76 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use quote::{ format_ident, quote };
+use syn::{ parse_macro_input, ItemFn, LitStr };
 
-pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
-    // Parse the arguments passed to the macro (if any)
-    // For demonstration, we're not using args, but you could use it to get, for example, a path
-    let _args = parse_macro_input!(args as syn::DeriveInput);
+use crabserve_pattern::parse_pattern;
+
+pub fn route(method: &str, args: TokenStream, input: TokenStream) -> TokenStream {
+    let path = match syn::parse::<LitStr>(args) {
+        Ok(path) => path,
+        Err(e) => {
+            return syn::Error
+                ::new(
+                    e.span(),
+                    format!(
+                        "expected a route path string, e.g. #[{}(\"/users/:id\")]",
+                        method.to_lowercase()
+                    )
+                )
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    if let Err(e) = parse_pattern(&path.value()) {
+        return syn::Error::new_spanned(&path, e).to_compile_error().into();
+    }
 
-    // Parse the function the macro is attached to
     let function = parse_macro_input!(input as ItemFn);
 
-    // Implement your logic here. For demonstration, we're simply returning the original function
-    // without modifications. In a real scenario, you might modify the function or generate additional
-    // code based on the args.
+    if function.sig.asyncness.is_none() {
+        return syn::Error
+            ::new_spanned(function.sig.fn_token, "route handlers must be `async fn`")
+            .to_compile_error()
+            .into();
+    }
+
+    let vis = &function.vis;
+    let name = &function.sig.ident;
+    let service = format_ident!("{}Route", to_camel_case(&name.to_string()), span = name.span());
+    let method = format_ident!("{}", method);
+
+    // The handler stays callable as written; `get_user` is registered with
+    // `Router::add_service(GetUserRoute)`.
+    quote!(
+        #function
+
+        #vis struct #service;
+
+        impl ::CrabServe::router::route::RouteService for #service {
+            fn method(&self) -> ::CrabServe::http_core::http_types::HttpMethods {
+                ::CrabServe::http_core::http_types::HttpMethods::#method
+            }
+
+            fn path(&self) -> &'static str {
+                #path
+            }
+
+            fn register(
+                self,
+                router: ::CrabServe::router::router::Router
+            ) -> ::CrabServe::router::router::Router {
+                router.add_route(::CrabServe::http_core::http_types::HttpMethods::#method, #path, #name)
+            }
+        }
+    ).into()
+}
 
-    // Generate the output TokenStream
-    quote!(#function).into()
-}
\ No newline at end of file
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+//! Route pattern parsing shared by the router's matcher, which checks
+//! patterns at run time, and the route macros, which check them at compile time.
+
+use thiserror::Error;
+
+/// Why a route pattern was rejected, by `Matcher::insert` at run time or by
+/// the route macros at compile time.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    #[error("Route pattern must start with '/': '{0}'")] MissingLeadingSlash(String),
+    #[error("Route pattern contains invalid character {0:?}")] InvalidCharacter(char),
+    #[error("Empty parameter name in segment '{0}'")] EmptyParamName(String),
+    #[error("Invalid parameter name in segment '{0}'")] InvalidParamName(String),
+    #[error("Invalid characters in static segment '{0}'")] InvalidStaticSegment(String),
+    #[error("Wildcard '{0}' must be the last segment")] WildcardNotLast(String),
+    #[error("Optional segment '{0}' must be the last segment")] OptionalNotLast(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Static(String),
+    Param(String),
+    Wildcard(String),
+}
+
+/// Splits a route pattern into its segments, and tells whether the last
+/// one is an optional parameter.
+pub fn parse_pattern(pattern: &str) -> Result<(Vec<Segment>, bool), PatternError> {
+    if !pattern.starts_with('/') {
+        return Err(PatternError::MissingLeadingSlash(pattern.to_string()));
+    }
+    if let Some(c) = pattern.chars().find(|c| c.is_whitespace() || *c == '#') {
+        return Err(PatternError::InvalidCharacter(c));
+    }
+
+    let raw_segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let mut segments = Vec::with_capacity(raw_segments.len());
+    let mut optional = false;
+
+    for (index, raw) in raw_segments.iter().enumerate() {
+        let is_last = index == raw_segments.len() - 1;
+
+        if let Some(name) = raw.strip_prefix(':') {
+            let name = match name.strip_suffix('?') {
+                Some(name) if is_last => {
+                    optional = true;
+                    name
+                }
+                Some(_) => {
+                    return Err(PatternError::OptionalNotLast(raw.to_string()));
+                }
+                None => name,
+            };
+            validate_param_name(raw, name)?;
+            segments.push(Segment::Param(name.to_string()));
+        } else if let Some(name) = raw.strip_prefix('*') {
+            validate_param_name(raw, name)?;
+            if !is_last {
+                return Err(PatternError::WildcardNotLast(raw.to_string()));
+            }
+            segments.push(Segment::Wildcard(name.to_string()));
+        } else if raw.contains(['?', ':', '*']) {
+            return Err(PatternError::InvalidStaticSegment(raw.to_string()));
+        } else {
+            segments.push(Segment::Static(raw.to_string()));
+        }
+    }
+
+    Ok((segments, optional))
+}
+
+fn validate_param_name(segment: &str, name: &str) -> Result<(), PatternError> {
+    if name.is_empty() {
+        return Err(PatternError::EmptyParamName(segment.to_string()));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(PatternError::InvalidParamName(segment.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::io::{ Read, Write };
+use async_trait::async_trait;
+use flate2::{
+    read::{ GzDecoder, ZlibDecoder },
+    write::{ GzEncoder, ZlibEncoder },
+    Compression as Level,
+};
+
+use crate::http_core::{
+    headers::{ HeaderName, HeaderValue },
+    body::Body,
+    http_types::MediaType,
+    negotiation::{ negotiate_encoding, parse_quality_list },
+    request::Request,
+    response::Response,
+};
+use super::middleware::{ Middleware, Next };
+
+/// Content codings in the order the server prefers them.
+const ENCODINGS: [&str; 3] = ["br", "gzip", "deflate"];
+
+/// Compresses response bodies according to `Accept-Encoding` and decodes
+/// compressed request bodies before they reach the handler.
+#[derive(Debug, Clone)]
+pub struct Compression {
+    min_size: usize,
+    level: u32,
+    max_decompressed_size: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            min_size: 1024,
+            level: 6,
+            max_decompressed_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bodies smaller than this are sent uncompressed.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Compression level from 0 (fastest) to 9 (smallest).
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = level.min(9);
+        self
+    }
+
+    pub fn max_decompressed_size(mut self, max_decompressed_size: usize) -> Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self
+    }
+
+    fn decode_request(&self, request: &mut Request) -> Result<(), Response> {
+        let Some(encoding) = request.headers.get_str("Content-Encoding") else {
+            return Ok(());
+        };
+        let encoding = encoding.trim().to_ascii_lowercase();
+        if encoding == "identity" {
+            request.headers.remove("Content-Encoding");
+            return Ok(());
+        }
+
+        let body = request.body.as_slice();
+        let reader: Box<dyn Read + '_> = match encoding.as_str() {
+            "gzip" | "x-gzip" => Box::new(GzDecoder::new(body)),
+            "deflate" => Box::new(ZlibDecoder::new(body)),
+            "br" => Box::new(brotli::Decompressor::new(body, 4096)),
+            _ => {
+                return Err(
+                    Response::new(415)
+                        .add_header("Accept-Encoding", "gzip, deflate, br")
+                        .add_body(format!("Unsupported Content-Encoding: {}", encoding).into_bytes())
+                );
+            }
+        };
+
+        // Reading one byte past the limit tells an oversized body from one that fits exactly.
+        let mut decoded = Vec::new();
+        reader
+            .take((self.max_decompressed_size as u64) + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|e|
+                Response::new(400).add_body(format!("Failed to decode {} body: {}", encoding, e).into_bytes())
+            )?;
+        if decoded.len() > self.max_decompressed_size {
+            return Err(Response::new(413).add_body(b"Decompressed body is too large".to_vec()));
+        }
+
+        request.headers.remove("Content-Encoding");
+        request.headers.insert(HeaderName::from_static("Content-Length"), HeaderValue::from(decoded.len()));
+        request.body = decoded;
+        Ok(())
+    }
+
+    fn encode_response(&self, response: &mut Response, encoding: Option<&'static str>) {
+        if !is_compressible(response) {
+            return;
+        }
+        response.headers.append(HeaderName::from_static("Vary"), HeaderValue::from_static("Accept-Encoding"));
+
+        // Files and streams are sent as they are read rather than compressed up front.
+        let Some(body) = response.body.as_bytes() else {
+            return;
+        };
+        if body.len() < self.min_size {
+            return;
+        }
+        let Some(encoding) = encoding else {
+            return;
+        };
+
+        let encoded = match encoding {
+            "br" => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, self.level, 22);
+                encoder.write_all(body).map(|_| encoder.into_inner())
+            }
+            "gzip" => {
+                let mut encoder = GzEncoder::new(Vec::new(), Level::new(self.level));
+                encoder.write_all(body).and_then(|_| encoder.finish())
+            }
+            _ => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Level::new(self.level));
+                encoder.write_all(body).and_then(|_| encoder.finish())
+            }
+        };
+
+        if let Ok(encoded) = encoded {
+            response.body = Body::Bytes(encoded);
+            response.headers.remove("Content-Length");
+            response.headers.insert(HeaderName::from_static("Content-Encoding"), HeaderValue::from_static(encoding));
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for Compression {
+    async fn handle(&self, mut request: Request, next: Next) -> Response {
+        if let Err(response) = self.decode_request(&mut request) {
+            return response;
+        }
+
+        // Without Accept-Encoding any coding is allowed, but identity is the safe
+        // choice; an empty one allows identity only (RFC 9110 section 12.5.3).
+        let accepted = parse_quality_list(&request.headers, "Accept-Encoding");
+        let encoding = if accepted.is_empty() {
+            None
+        } else {
+            negotiate_encoding(&accepted, &ENCODINGS).map(|index| ENCODINGS[index])
+        };
+
+        let mut response = next.run(request).await;
+        self.encode_response(&mut response, encoding);
+        response
+    }
+}
+
+fn is_compressible(response: &Response) -> bool {
+    let status_code = response.status_code();
+    if status_code.is_informational() || status_code == 204 || status_code == 304 {
+        return false;
+    }
+    if response.has_header("Content-Encoding") || response.headers.contains_token("Cache-Control", "no-transform") {
+        return false;
+    }
+
+    response.headers
+        .get_str("Content-Type")
+        .and_then(|value| value.parse::<MediaType>().ok())
+        .is_none_or(|media_type| !is_precompressed(&media_type))
+}
+
+// Formats that are already compressed gain nothing from a second pass.
+fn is_precompressed(media_type: &MediaType) -> bool {
+    match media_type.type_() {
+        "image" => media_type.subtype() != "svg+xml" && media_type.subtype() != "bmp",
+        "audio" | "video" => true,
+        "font" => matches!(media_type.subtype(), "woff" | "woff2"),
+        "application" =>
+            matches!(
+                media_type.subtype(),
+                "zip" | "gzip" | "x-gzip" | "x-bzip2" | "x-xz" | "zstd" | "x-7z-compressed" | "pdf" | "octet-stream"
+            ),
+        _ => false,
+    }
+}
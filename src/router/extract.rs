@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use serde::{ de::DeserializeOwned, Serialize };
+use serde_json::json;
+
+use crate::http_core::{
+    body_stream::{ BodyReader, BodyStream },
+    http_types::{ ContentType, MediaType },
+    multipart::{ boundary, Multipart, MultipartConfig, MultipartError },
+    request::{ HttpRequest, Request },
+    response::Response,
+};
+use super::form;
+use super::handler::{ FromRequest, IntoResponse };
+
+/// Query string deserialized into `T`; rejects with 400 when it does not fit.
+#[derive(Debug, Clone)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned + Send> FromRequest for Query<T> {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        serde_urlencoded
+            ::from_str(&request.query)
+            .map(Query)
+            .map_err(|e|
+                Response::new(400).add_body(
+                    format!("Failed to deserialize query string: {}", e).into_bytes()
+                )
+            )
+    }
+}
+
+/// JSON request body deserialized into `T`, or a JSON response body when returned.
+///
+/// Requests must declare `application/json` (or a `+json` suffix) with a
+/// UTF-8 charset, otherwise they are rejected with 415; bodies that fail to
+/// deserialize are rejected with 400.
+#[derive(Debug, Clone)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned + Send> FromRequest for Json<T> {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        if let Err(detail) = check_json_content_type(request.content_type()) {
+            return Err(json_rejection(415, "unsupported_media_type", detail, None));
+        }
+
+        let body = request.body.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&request.body);
+        serde_json
+            ::from_slice(body)
+            .map(Json)
+            .map_err(|e| {
+                let kind = match e.classify() {
+                    serde_json::error::Category::Data => "invalid_json_data",
+                    _ => "invalid_json_syntax",
+                };
+                json_rejection(400, kind, e.to_string(), Some((e.line(), e.column())))
+            })
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match Response::new(200).set_json_body(&self.0) {
+            Ok(response) => response,
+            Err(e) => Response::new(500).add_body(format!("Failed to serialize JSON: {}", e).into_bytes()),
+        }
+    }
+}
+
+/// `application/x-www-form-urlencoded` body deserialized into `T`.
+///
+/// A `Vec` field takes every value sent for its key, whether one or several,
+/// and bracketed keys such as `address[city]` or `tags[]` build nested
+/// structures.
+#[derive(Debug, Clone)]
+pub struct Form<T>(pub T);
+
+const FORM_MAX_DEPTH: usize = 5;
+
+#[async_trait]
+impl<T: DeserializeOwned + Send> FromRequest for Form<T> {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        if !request.content_type().is_some_and(|media_type| media_type.is_form_urlencoded()) {
+            return Err(
+                Response::new(415).add_body(
+                    b"Expected Content-Type application/x-www-form-urlencoded".to_vec()
+                )
+            );
+        }
+
+        let body = std::str
+            ::from_utf8(&request.body)
+            .map_err(|_| Response::new(400).add_body(b"Form body is not valid UTF-8".to_vec()))?;
+
+        form::from_str(body, FORM_MAX_DEPTH)
+            .map(Form)
+            .map_err(|e|
+                Response::new(400).add_body(format!("Failed to deserialize form body: {}", e).into_bytes())
+            )
+    }
+}
+
+/// `multipart/form-data` body, parsed part by part as the handler asks for them.
+///
+/// Reads the request's `BodyStream` when the server left one, and the
+/// buffered body otherwise. Limits come from a `MultipartConfig` in the
+/// router state, if any.
+#[async_trait]
+impl FromRequest for Multipart<BodyReader> {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        let content_type = request.headers.get_str("Content-Type").unwrap_or_default();
+        let Some(boundary) = boundary(content_type) else {
+            return Err(MultipartError::MissingBoundaryError.into_response());
+        };
+        let config = request.extensions.get::<MultipartConfig>().cloned().unwrap_or_default();
+
+        let body = request.extensions
+            .get::<BodyStream>()
+            .and_then(BodyStream::take)
+            .unwrap_or_else(|| BodyReader::from(request.body.clone()));
+
+        Ok(Multipart::new(body, &boundary, config))
+    }
+}
+
+impl IntoResponse for MultipartError {
+    fn into_response(self) -> Response {
+        Response::new(self.status_code()).add_body(self.to_string().into_bytes())
+    }
+}
+
+fn check_json_content_type(content_type: Option<MediaType>) -> Result<(), String> {
+    let Some(media_type) = content_type.filter(MediaType::is_json) else {
+        return Err("Expected Content-Type application/json".to_string());
+    };
+
+    match media_type.charset() {
+        Some(charset) if !charset.eq_ignore_ascii_case("utf-8") && !charset.eq_ignore_ascii_case("utf8") =>
+            Err(format!("Unsupported charset '{}', expected utf-8", charset)),
+        _ => Ok(()),
+    }
+}
+
+fn json_rejection(status_code: u16, kind: &str, message: String, position: Option<(usize, usize)>) -> Response {
+    let mut error = json!({ "status": status_code, "error": kind, "message": message });
+    if let Some((line, column)) = position {
+        error["line"] = json!(line);
+        error["column"] = json!(column);
+    }
+
+    Response::new(status_code)
+        .set_content_type(ContentType::ApplicationJson)
+        .add_body(error.to_string().into_bytes())
+}
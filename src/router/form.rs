@@ -0,0 +1,315 @@
+use serde::de::{
+    self,
+    value::{ Error, MapDeserializer, SeqDeserializer, StrDeserializer },
+    DeserializeOwned,
+    Deserializer,
+    IntoDeserializer,
+    Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+/// Parsed `application/x-www-form-urlencoded` data, with bracketed keys
+/// such as `address[city]` or `tags[]` nested up to `max_depth` levels.
+///
+/// Every key holds a list of values, so a field deserializes as a sequence
+/// whether the browser sent one value for it or several.
+pub(crate) fn from_str<T: DeserializeOwned>(body: &str, max_depth: usize) -> Result<T, Error> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(body).map_err(de::Error::custom)?;
+
+    let mut root = Vec::new();
+    for (key, value) in pairs {
+        let path = split_key(&key)?;
+        if path.len() > max_depth + 1 {
+            return Err(de::Error::custom(format!("Key '{}' is nested too deeply", key)));
+        }
+        insert(&mut root, &path, value).map_err(|_| de::Error::custom(format!("Key '{}' conflicts with another key", key)))?;
+    }
+    T::deserialize(FormValue::Map(root))
+}
+
+#[derive(Debug)]
+enum FormValue {
+    Values(Vec<String>),
+    Map(Vec<(String, FormValue)>),
+}
+
+// `a[b][]` becomes `["a", "b"]`; an empty trailing pair only marks a list.
+fn split_key(key: &str) -> Result<Vec<&str>, Error> {
+    let malformed = || de::Error::custom(format!("Malformed key '{}'", key));
+    let (name, mut rest) = match key.find('[') {
+        Some(start) => (&key[..start], &key[start..]),
+        None => (key, ""),
+    };
+    if name.is_empty() {
+        return Err(malformed());
+    }
+
+    let mut path = vec![name];
+    while !rest.is_empty() {
+        let end = rest.find(']').filter(|_| rest.starts_with('[')).ok_or_else(malformed)?;
+        let segment = &rest[1..end];
+        rest = &rest[end + 1..];
+        if segment.is_empty() {
+            if !rest.is_empty() {
+                return Err(malformed());
+            }
+        } else {
+            path.push(segment);
+        }
+    }
+    Ok(path)
+}
+
+fn insert(map: &mut Vec<(String, FormValue)>, path: &[&str], value: String) -> Result<(), ()> {
+    let position = match map.iter().position(|(key, _)| key == path[0]) {
+        Some(position) => position,
+        None => {
+            let empty = if path.len() == 1 { FormValue::Values(Vec::new()) } else { FormValue::Map(Vec::new()) };
+            map.push((path[0].to_string(), empty));
+            map.len() - 1
+        }
+    };
+    match (&mut map[position].1, path.len()) {
+        (FormValue::Values(values), 1) => {
+            values.push(value);
+            Ok(())
+        }
+        (FormValue::Map(entries), length) if length > 1 => insert(entries, &path[1..], value),
+        _ => Err(()),
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for FormValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for FormValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            FormValue::Values(values) if values.len() == 1 => Part(values.into_iter().next().unwrap()).deserialize_any(visitor),
+            FormValue::Values(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter().map(Part))),
+            FormValue::Map(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            FormValue::Values(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter().map(Part))),
+            // Indexed keys such as `tags[0]` and `tags[1]`, in index order.
+            FormValue::Map(entries) => {
+                let mut indexed = entries
+                    .into_iter()
+                    .map(|(key, value)| key.parse::<usize>().map(|index| (index, value)))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| de::Error::invalid_type(de::Unexpected::Map, &visitor))?;
+                indexed.sort_by_key(|(index, _)| *index);
+                visitor.visit_seq(SeqDeserializer::new(indexed.into_iter().map(|(_, value)| value)))
+            }
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            FormValue::Map(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+            FormValue::Values(_) => Err(de::Error::invalid_type(de::Unexpected::Str("value"), &visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    // Everything else takes exactly one value.
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_bool(visitor)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_u64(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_f64(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+}
+
+impl FormValue {
+    fn single(self) -> Result<Part, Error> {
+        match self {
+            FormValue::Values(values) if values.len() == 1 => Ok(Part(values.into_iter().next().unwrap())),
+            FormValue::Values(_) => Err(de::Error::custom("Expected a single value but the key is repeated")),
+            FormValue::Map(_) => Err(de::Error::custom("Expected a single value but found nested keys")),
+        }
+    }
+}
+
+/// One value, parsed into whatever type the field asks for.
+struct Part(String);
+
+impl<'de> IntoDeserializer<'de, Error> for Part {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Part {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Error> {
+        StrDeserializer::<Error>::new(&self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
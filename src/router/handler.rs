@@ -0,0 +1,151 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use crate::http_core::{ http_types::{ ContentType, StatusCode }, request::Request, response::Response };
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub type BoxedHandler = Arc<dyn Fn(Request) -> BoxFuture<'static, Response> + Send + Sync>;
+
+pub trait IntoResponse {
+    fn into_response(self) -> Response;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::new(200)
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response {
+        self.to_string().into_response()
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        Response::new(200)
+            .set_content_type(ContentType::TextPlain)
+            .add_body(self.into_bytes())
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        Response::new(200).add_header("Content-Type", "application/octet-stream").add_body(self)
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (u16, T) {
+    fn into_response(self) -> Response {
+        let mut response = self.1.into_response();
+        response.status_code = self.0.into();
+        response
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (StatusCode, T) {
+    fn into_response(self) -> Response {
+        let mut response = self.1.into_response();
+        response.status_code = self.0;
+        response
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
+
+/// Extracts a handler argument from the incoming request, or rejects it with a response.
+#[async_trait]
+pub trait FromRequest: Sized {
+    async fn from_request(request: &Request) -> Result<Self, Response>;
+}
+
+#[async_trait]
+impl FromRequest for Request {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        Ok(request.clone())
+    }
+}
+
+/// Shared state registered with `Router::with_state`.
+#[derive(Debug, Clone)]
+pub struct State<T>(pub T);
+
+#[async_trait]
+impl<T: Clone + Send + Sync + 'static> FromRequest for State<T> {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        match request.extensions.get::<T>() {
+            Some(state) => Ok(State(state.clone())),
+            None =>
+                Err(
+                    Response::new(500).add_body(
+                        format!("Missing state of type {}", std::any::type_name::<T>()).into_bytes()
+                    )
+                ),
+        }
+    }
+}
+
+/// Async function or closure usable as a route endpoint.
+///
+/// Implemented for `Fn` taking up to eight `FromRequest` arguments and
+/// returning a future whose output implements `IntoResponse`.
+pub trait Handler<Args>: Clone + Send + Sync + 'static {
+    fn call(&self, request: Request) -> BoxFuture<'static, Response>;
+}
+
+macro_rules! impl_handler {
+    ($($arg:ident),*) => {
+        #[allow(non_snake_case, unused_variables)]
+        impl<F, Fut, Res, $($arg,)*> Handler<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = Res> + Send + 'static,
+            Res: IntoResponse,
+            $($arg: FromRequest + Send + 'static,)*
+        {
+            fn call(&self, request: Request) -> BoxFuture<'static, Response> {
+                let handler = self.clone();
+                Box::pin(async move {
+                    $(
+                        let $arg = match $arg::from_request(&request).await {
+                            Ok(value) => value,
+                            Err(rejection) => return rejection,
+                        };
+                    )*
+                    handler($($arg),*).await.into_response()
+                })
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+impl_handler!(T1, T2, T3, T4, T5);
+impl_handler!(T1, T2, T3, T4, T5, T6);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+
+pub fn into_boxed_handler<H, Args>(handler: H) -> BoxedHandler where H: Handler<Args> {
+    Arc::new(move |request| handler.call(request))
+}
@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use percent_encoding::percent_decode_str;
+use thiserror::Error;
+
+use crabserve_pattern::{ parse_pattern, PatternError, Segment };
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MatcherError {
+    #[error(transparent)] InvalidPattern(#[from] PatternError),
+    #[error("Parameter '{0}' conflicts with existing parameter '{1}'")] ConflictingParam(
+        String,
+        String,
+    ),
+    #[error("Route '{0}' is already registered")] DuplicateRoute(String),
+}
+
+#[derive(Debug)]
+pub struct Match<'a, T> {
+    pub value: &'a T,
+    pub params: Vec<(String, String)>,
+}
+
+/// Segment-based radix tree resolving request paths to registered values.
+///
+/// Patterns support named parameters (`/users/:id`), a trailing optional
+/// parameter (`/posts/:page?`) and a trailing catch-all (`/files/*rest`).
+/// Static segments win over parameters, parameters over wildcards.
+#[derive(Debug, Clone)]
+pub struct Matcher<T> {
+    root: Node<T>,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    statics: HashMap<String, Node<T>>,
+    param: Option<(String, Box<Node<T>>)>,
+    wildcard: Option<(String, T)>,
+    value: Option<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            statics: HashMap::new(),
+            param: None,
+            wildcard: None,
+            value: None,
+        }
+    }
+}
+
+impl<T> Default for Matcher<T> {
+    fn default() -> Self {
+        Self { root: Node::default() }
+    }
+}
+
+impl<T: Clone> Matcher<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, pattern: &str, value: T) -> Result<(), MatcherError> {
+        let (segments, optional) = parse_pattern(pattern)?;
+
+        if optional {
+            let required = &segments[..segments.len() - 1];
+            if self.root.find_exact(required).is_some() {
+                return Err(MatcherError::DuplicateRoute(pattern.to_string()));
+            }
+            self.root.insert(&segments, value.clone(), pattern)?;
+            self.root.insert(required, value, pattern)
+        } else {
+            self.root.insert(&segments, value, pattern)
+        }
+    }
+
+    /// Looks up a path as it was sent. Segments are split before they are
+    /// percent-decoded, so `%2F` can't add a segment.
+    pub fn at<'a>(&'a self, path: &str) -> Option<Match<'a, T>> {
+        let decoded: Vec<Cow<'_, str>> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy())
+            .collect();
+        let segments: Vec<&str> = decoded.iter().map(AsRef::as_ref).collect();
+        let mut params = Vec::new();
+
+        self.root.lookup(&segments, &mut params).map(|value| Match {
+            value,
+            params: params
+                .into_iter()
+                .rev()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        })
+    }
+}
+
+impl<T> Node<T> {
+    fn insert(&mut self, segments: &[Segment], value: T, pattern: &str) -> Result<(), MatcherError> {
+        let Some((segment, rest)) = segments.split_first() else {
+            if self.value.is_some() {
+                return Err(MatcherError::DuplicateRoute(pattern.to_string()));
+            }
+            self.value = Some(value);
+            return Ok(());
+        };
+
+        match segment {
+            Segment::Static(name) => {
+                self.statics.entry(name.clone()).or_default().insert(rest, value, pattern)
+            }
+            Segment::Param(name) => {
+                let (existing, child) = self.param.get_or_insert_with(|| {
+                    (name.clone(), Box::default())
+                });
+                if existing != name {
+                    return Err(MatcherError::ConflictingParam(name.clone(), existing.clone()));
+                }
+                child.insert(rest, value, pattern)
+            }
+            Segment::Wildcard(name) => {
+                if self.wildcard.is_some() {
+                    return Err(MatcherError::DuplicateRoute(pattern.to_string()));
+                }
+                self.wildcard = Some((name.clone(), value));
+                Ok(())
+            }
+        }
+    }
+
+    fn find_exact(&self, segments: &[Segment]) -> Option<&T> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.value.as_ref();
+        };
+
+        match segment {
+            Segment::Static(name) => self.statics.get(name)?.find_exact(rest),
+            Segment::Param(_) => self.param.as_ref()?.1.find_exact(rest),
+            Segment::Wildcard(_) => self.wildcard.as_ref().map(|(_, value)| value),
+        }
+    }
+
+    // Params are pushed while unwinding, so they come out in reverse order.
+    fn lookup<'a>(&'a self, segments: &[&str], params: &mut Vec<(&'a str, String)>) -> Option<&'a T> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.value.as_ref();
+        };
+
+        if let Some(value) = self.statics.get(*segment).and_then(|child| child.lookup(rest, params)) {
+            return Some(value);
+        }
+
+        if let Some((name, child)) = &self.param {
+            if let Some(value) = child.lookup(rest, params) {
+                params.push((name, segment.to_string()));
+                return Some(value);
+            }
+        }
+
+        self.wildcard.as_ref().map(|(name, value)| {
+            params.push((name, segments.join("/")));
+            value
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use crate::http_core::{ request::Request, response::Response };
+use super::handler::BoxedHandler;
+
+/// Async layer around a handler.
+///
+/// A middleware may inspect or modify the request, short-circuit by returning
+/// a response without calling `next`, or post-process the response that
+/// `next.run(request)` produces.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn handle(&self, request: Request, next: Next) -> Response;
+}
+
+#[async_trait]
+impl<F, Fut> Middleware for F
+    where
+        F: Fn(Request, Next) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response> + Send + 'static
+{
+    async fn handle(&self, request: Request, next: Next) -> Response {
+        self(request, next).await
+    }
+}
+
+impl fmt::Debug for dyn Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Middleware")
+    }
+}
+
+/// The remainder of the middleware chain, ending with the endpoint handler.
+#[derive(Clone)]
+pub struct Next {
+    middleware: Arc<[Arc<dyn Middleware>]>,
+    index: usize,
+    endpoint: BoxedHandler,
+}
+
+impl Next {
+    pub fn new(middleware: Arc<[Arc<dyn Middleware>]>, endpoint: BoxedHandler) -> Self {
+        Self { middleware, index: 0, endpoint }
+    }
+
+    pub async fn run(mut self, request: Request) -> Response {
+        match self.middleware.get(self.index).cloned() {
+            Some(middleware) => {
+                self.index += 1;
+                middleware.handle(request, self).await
+            }
+            None => (self.endpoint)(request).await,
+        }
+    }
+}
+
+impl fmt::Debug for Next {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining", &(self.middleware.len() - self.index))
+            .finish_non_exhaustive()
+    }
+}
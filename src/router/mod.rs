@@ -1,5 +1,11 @@
 pub mod route;
 pub mod handler;
 pub mod middleware;
+#[allow(clippy::module_inception)]
 pub mod router;
 pub mod matcher;
+pub use crabserve_pattern as pattern;
+pub mod extract;
+mod form;
+pub mod compression;
+pub mod static_files;
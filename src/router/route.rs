@@ -1,15 +1,32 @@
-use crate::http_core::{ response::Response, http_types::HttpMethods };
+use std::fmt;
+use std::sync::Arc;
 
-pub type RouterHandler = fn() -> Response;
+use crate::http_core::http_types::HttpMethods;
+use super::handler::BoxedHandler;
+use super::middleware::Middleware;
+use super::router::Router;
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct RouteKey {
     pub method: HttpMethods,
     pub path: String,
 }
 
+/// Route item generated by the `#[get]`, `#[post]`, ... attribute macros.
+pub trait RouteService {
+    fn method(&self) -> HttpMethods;
+    fn path(&self) -> &'static str;
+    fn register(self, router: Router) -> Router;
+}
+
 #[derive(Clone)]
 pub struct Route {
-    pub handler: RouterHandler,
-    pub middleware: Vec<fn() -> ()>,
-}
\ No newline at end of file
+    pub handler: BoxedHandler,
+    pub middleware: Vec<Arc<dyn Middleware>>,
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route").field("middleware", &self.middleware).finish_non_exhaustive()
+    }
+}
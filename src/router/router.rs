@@ -1,24 +1,264 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::http_core::response::Response;
-use super::route::{ Route, RouteKey, RouterHandler };
+use crate::http_core::{
+    extensions::Extensions,
+    http_types::{ HttpMethods, StatusCode },
+    request::{ HttpRequest, Request },
+    response::Response,
+};
+use super::handler::{ into_boxed_handler, BoxFuture, BoxedHandler, Handler };
+use super::matcher::Matcher;
+use super::middleware::{ Middleware, Next };
+use super::route::{ Route, RouteKey, RouteService };
+use super::static_files::StaticFiles;
+use crate::tls::TlsIdentity;
 
-struct Router {
-    routes: Option<HashMap<RouteKey, Route>>,
+#[derive(Debug, Clone)]
+pub struct Router {
+    routes: HashMap<RouteKey, Route>,
+    matcher: Matcher<String>,
+    state: Extensions,
+    middleware: Vec<Arc<dyn Middleware>>,
     path: String,
     pub error_handlers: Option<HashMap<u16, fn() -> Response>>,
     pub ssl_certificate: Option<String>,
     pub ssl_private_key: Option<String>,
+    ssl_pem: Option<TlsIdentity>,
 }
 
 impl Router {
-    fn new(path: String) -> Self {
+    pub fn new(path: String) -> Self {
         Self {
-            routes:None,
+            routes: HashMap::new(),
+            matcher: Matcher::new(),
+            state: Extensions::new(),
+            middleware: Vec::new(),
             path,
             error_handlers: None,
             ssl_certificate: None,
             ssl_private_key: None,
+            ssl_pem: None,
         }
     }
+
+    pub fn add_route<H, Args>(mut self, method: HttpMethods, path: &str, handler: H) -> Self
+        where H: Handler<Args>
+    {
+        let pattern = join_paths(&self.path, path);
+
+        if !self.routes.keys().any(|key| key.path == pattern) {
+            if let Err(e) = self.matcher.insert(&pattern, pattern.clone()) {
+                panic!("Invalid route '{}': {}", pattern, e);
+            }
+        }
+
+        let key = RouteKey { method, path: pattern };
+        self.routes.insert(key, Route {
+            handler: into_boxed_handler(handler),
+            middleware: Vec::new(),
+        });
+        self
+    }
+
+    pub fn add_service<S: RouteService>(self, service: S) -> Self {
+        service.register(self)
+    }
+
+    /// Serves `files` under `prefix`, e.g. `/assets/css/site.css` from `<root>/css/site.css`.
+    pub fn add_static_files(self, prefix: &str, files: StaticFiles) -> Self {
+        let files = Arc::new(files);
+        let handler = move |request: Request| {
+            let files = files.clone();
+            async move { files.serve(&request).await }
+        };
+
+        let wildcard = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.add_route(HttpMethods::GET, prefix, handler.clone()).add_route(HttpMethods::GET, &wildcard, handler)
+    }
+
+    pub fn add_route_middleware<M: Middleware>(
+        mut self,
+        method: HttpMethods,
+        path: &str,
+        middleware: M
+    ) -> Self {
+        let key = RouteKey { method, path: join_paths(&self.path, path) };
+        match self.routes.get_mut(&key) {
+            Some(route) => route.middleware.push(Arc::new(middleware)),
+            None => panic!("Route {} {} is not registered", key.method, key.path),
+        }
+        self
+    }
+
+    pub fn add_middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn with_state<T: Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.state.insert(state);
+        self
+    }
+
+    pub fn add_error_handler(mut self, status_code: u16, handler: fn() -> Response) -> Self {
+        self.error_handlers.get_or_insert_with(HashMap::new).insert(status_code, handler);
+        self
+    }
+
+    /// Serves this router over HTTPS with the PEM certificate chain and key at these paths.
+    pub fn with_ssl(mut self, certificate: &str, private_key: &str) -> Self {
+        self.ssl_certificate = Some(certificate.to_string());
+        self.ssl_private_key = Some(private_key.to_string());
+        self
+    }
+
+    /// Like `with_ssl`, with the PEM data already in memory.
+    pub fn with_ssl_pem(mut self, certificate: impl Into<Vec<u8>>, private_key: impl Into<Vec<u8>>) -> Self {
+        self.ssl_pem = Some(TlsIdentity::Pem {
+            certificate: certificate.into().into(),
+            private_key: private_key.into().into(),
+        });
+        self
+    }
+
+    pub fn tls_identity(&self) -> Option<TlsIdentity> {
+        if let Some(identity) = &self.ssl_pem {
+            return Some(identity.clone());
+        }
+        match (&self.ssl_certificate, &self.ssl_private_key) {
+            (Some(certificate), Some(private_key)) =>
+                Some(TlsIdentity::Files {
+                    certificate: certificate.into(),
+                    private_key: private_key.into(),
+                }),
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether a route of this router matches `path`, for any method.
+    pub fn has_route(&self, path: &str) -> bool {
+        self.matcher.at(path).is_some()
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let prefix = self.path.trim_end_matches('/');
+        prefix.is_empty() ||
+            path == prefix ||
+            path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    // Router middleware wraps everything the router answers, including its
+    // 404/405 responses; route middleware runs after it, closest to the handler.
+    pub async fn handle(&self, mut request: Request) -> Response {
+        request.extensions.extend(&self.state);
+
+        let mut middleware = self.middleware.clone();
+        let endpoint = match self.resolve(&mut request) {
+            Resolved::Route(route) => {
+                middleware.extend(route.middleware.iter().cloned());
+                route.handler.clone()
+            }
+            Resolved::Response(response) => respond_with(response),
+        };
+
+        Next::new(middleware.into(), endpoint).run(request).await
+    }
+
+    fn resolve(&self, request: &mut Request) -> Resolved<'_> {
+        if
+            matches!(request.method, HttpMethods::Extension(_)) &&
+            !self.routes.keys().any(|key| key.method == request.method)
+        {
+            return Resolved::Response(self.error_response(501));
+        }
+
+        let Some(matched) = self.matcher.at(request.raw_path()) else {
+            return Resolved::Response(self.error_response(404));
+        };
+        let pattern = matched.value;
+        request.params = matched.params.into_iter().collect();
+
+        let key = RouteKey { method: request.method.clone(), path: pattern.clone() };
+        if let Some(route) = self.routes.get(&key) {
+            return Resolved::Route(route);
+        }
+
+        // HEAD falls back to the GET handler; the server drops the body.
+        if request.method == HttpMethods::HEAD {
+            let key = RouteKey { method: HttpMethods::GET, path: pattern.clone() };
+            if let Some(route) = self.routes.get(&key) {
+                return Resolved::Route(route);
+            }
+        }
+
+        let allow = self.allowed_methods(pattern);
+        if request.method == HttpMethods::OPTIONS {
+            return Resolved::Response(Response::new(204).add_header("Allow", &allow));
+        }
+
+        Resolved::Response(self.error_response(405).add_header("Allow", &allow))
+    }
+
+    fn allowed_methods(&self, pattern: &str) -> String {
+        let mut methods: Vec<HttpMethods> = self.routes
+            .keys()
+            .filter(|key| key.path == pattern)
+            .map(|key| key.method.clone())
+            .collect();
+
+        if methods.contains(&HttpMethods::GET) && !methods.contains(&HttpMethods::HEAD) {
+            methods.push(HttpMethods::HEAD);
+        }
+        if !methods.contains(&HttpMethods::OPTIONS) {
+            methods.push(HttpMethods::OPTIONS);
+        }
+        methods.sort();
+
+        methods
+            .iter()
+            .map(HttpMethods::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn error_response(&self, status_code: u16) -> Response {
+        match self.error_handlers.as_ref().and_then(|handlers| handlers.get(&status_code)) {
+            Some(handler) => handler(),
+            None => default_error_response(status_code),
+        }
+    }
+}
+
+pub fn default_error_response(status_code: u16) -> Response {
+    let status_code = StatusCode::from(status_code);
+    let message = status_code.canonical_reason().unwrap_or("Internal Server Error");
+    Response::new(status_code).add_body(message.as_bytes().to_vec())
+}
+
+enum Resolved<'a> {
+    Route(&'a Route),
+    Response(Response),
+}
+
+fn respond_with(response: Response) -> BoxedHandler {
+    Arc::new(move |_| {
+        let response = response.clone();
+        Box::pin(async move { response }) as BoxFuture<'static, Response>
+    })
+}
+
+fn join_paths(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    let path = path.trim_start_matches('/');
+
+    if path.is_empty() {
+        if prefix.is_empty() { "/".to_string() } else { prefix.to_string() }
+    } else {
+        format!("{}/{}", prefix, path)
+    }
 }
@@ -0,0 +1,312 @@
+use std::fs::Metadata;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+use percent_encoding::{ utf8_percent_encode, AsciiSet, CONTROLS };
+
+use crate::http_core::{
+    headers::HeaderName,
+    http_types::ContentType,
+    request::{ HttpRequest, Request },
+    response::Response,
+};
+
+// What a decoded path must have encoded again to be sent as a URL path.
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+/// Serves files below a directory. Mount it with `Router::add_static_files`.
+///
+/// Only `GET` is registered; the router answers `HEAD` from it, and the
+/// server drops the body.
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    root: PathBuf,
+    index_files: Vec<String>,
+    max_age: Option<Duration>,
+}
+
+impl StaticFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index_files: vec!["index.html".to_string()],
+            max_age: None,
+        }
+    }
+
+    /// Files tried, in order, when a directory is requested.
+    pub fn index_files(mut self, index_files: &[&str]) -> Self {
+        self.index_files = index_files.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Sets `Cache-Control: public, max-age=...` on every file served.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub async fn serve(&self, request: &Request) -> Response {
+        let Some(mut path) = self.resolve(request.param("path").unwrap_or_default()) else {
+            return not_found();
+        };
+        let Ok(mut metadata) = tokio::fs::metadata(&path).await else {
+            return not_found();
+        };
+
+        if metadata.is_dir() {
+            // Relative links in an index page only work below a trailing slash.
+            if !request.path().ends_with('/') {
+                let mut location = format!("{}/", utf8_percent_encode(request.path(), PATH));
+                if !request.query().is_empty() {
+                    location = format!("{}?{}", location, request.query());
+                }
+                return match Response::new(301).try_add_header("Location", &location) {
+                    Ok(response) => response,
+                    Err(_) => not_found(),
+                };
+            }
+
+            let mut index = None;
+            for name in &self.index_files {
+                let candidate = path.join(name);
+                if let Ok(candidate_metadata) = tokio::fs::metadata(&candidate).await {
+                    if candidate_metadata.is_file() {
+                        index = Some((candidate, candidate_metadata));
+                        break;
+                    }
+                }
+            }
+            let Some((index_path, index_metadata)) = index else {
+                return not_found();
+            };
+            path = index_path;
+            metadata = index_metadata;
+        }
+
+        if !metadata.is_file() || !self.is_inside_root(&path).await {
+            return not_found();
+        }
+
+        self.serve_file(request, &path, &metadata).await
+    }
+
+    // Rejects anything that could leave the root: `..`, absolute components
+    // and Windows separators or drive prefixes smuggled into a segment.
+    fn resolve(&self, relative: &str) -> Option<PathBuf> {
+        let mut path = self.root.clone();
+        for segment in relative.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    return None;
+                }
+                segment if segment.contains(['\\', ':', '\0']) => {
+                    return None;
+                }
+                segment => path.push(segment),
+            }
+        }
+        Some(path)
+    }
+
+    // Symlinks inside the root may still point outside of it.
+    async fn is_inside_root(&self, path: &Path) -> bool {
+        match (tokio::fs::canonicalize(&self.root).await, tokio::fs::canonicalize(path).await) {
+            (Ok(root), Ok(path)) => path.starts_with(root),
+            _ => false,
+        }
+    }
+
+    async fn serve_file(&self, request: &Request, path: &Path, metadata: &Metadata) -> Response {
+        let length = metadata.len();
+        let modified = metadata.modified().ok();
+        let etag = entity_tag(length, modified);
+        let last_modified = modified.map(httpdate::fmt_http_date);
+
+        let mut response = Response::new(200)
+            .set_content_type(
+                ContentType::from_extension(path.extension().and_then(|ext| ext.to_str()).unwrap_or_default())
+            )
+            .add_header("Accept-Ranges", "bytes")
+            .add_header("ETag", &etag);
+        if let Some(last_modified) = &last_modified {
+            response = response.add_header("Last-Modified", last_modified);
+        }
+        if let Some(max_age) = self.max_age {
+            response = response.add_header("Cache-Control", &format!("public, max-age={}", max_age.as_secs()));
+        }
+
+        match check_preconditions(request, &etag, modified) {
+            Precondition::Proceed => {}
+            Precondition::NotModified => {
+                let mut not_modified = Response::new(304);
+                for name in ["ETag", "Last-Modified", "Cache-Control"] {
+                    if let Some(value) = response.headers.get(name) {
+                        not_modified.headers.insert(HeaderName::from_static(name), value.clone());
+                    }
+                }
+                return not_modified;
+            }
+            Precondition::Failed => {
+                return Response::new(412);
+            }
+        }
+
+        let range = match request.headers.get_str("Range") {
+            Some(range) if if_range_matches(request, &etag, last_modified.as_deref()) =>
+                parse_range(range, length),
+            _ => RangeRequest::Full,
+        };
+
+        let (offset, size) = match range {
+            RangeRequest::Full => (0, length),
+            RangeRequest::Partial(start, end) => {
+                response.status_code = 206.into();
+                response = response.add_header("Content-Range", &format!("bytes {}-{}/{}", start, end, length));
+                (start, end - start + 1)
+            }
+            RangeRequest::Unsatisfiable => {
+                return Response::new(416)
+                    .add_header("Content-Range", &format!("bytes */{}", length))
+                    .add_header("Accept-Ranges", "bytes");
+            }
+        };
+
+        match tokio::fs::File::open(path).await {
+            Ok(file) => response.add_file_body(file.into_std().await, offset, size),
+            Err(_) => not_found(),
+        }
+    }
+}
+
+fn not_found() -> Response {
+    Response::new(404).add_body(b"Not Found".to_vec())
+}
+
+fn entity_tag(length: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("\"{:x}-{:x}\"", length, modified)
+}
+
+enum Precondition {
+    Proceed,
+    NotModified,
+    Failed,
+}
+
+// RFC 9110 section 13.2.2: If-Match, then If-Unmodified-Since, then
+// If-None-Match, then If-Modified-Since.
+fn check_preconditions(request: &Request, etag: &str, modified: Option<SystemTime>) -> Precondition {
+    let modified_secs = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs());
+    let header_secs = |name: &str| {
+        request.headers
+            .get_str(name)
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+            .and_then(|date| date.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+    };
+
+    if let Some(if_match) = request.headers.get_str("If-Match") {
+        if !etag_list_matches(if_match, etag, false) {
+            return Precondition::Failed;
+        }
+    } else if let (Some(since), Some(modified)) = (header_secs("If-Unmodified-Since"), modified_secs) {
+        if modified > since {
+            return Precondition::Failed;
+        }
+    }
+
+    if let Some(if_none_match) = request.headers.get_str("If-None-Match") {
+        if etag_list_matches(if_none_match, etag, true) {
+            return Precondition::NotModified;
+        }
+    } else if let (Some(since), Some(modified)) = (header_secs("If-Modified-Since"), modified_secs) {
+        if modified <= since {
+            return Precondition::NotModified;
+        }
+    }
+
+    Precondition::Proceed
+}
+
+fn etag_list_matches(list: &str, etag: &str, weak: bool) -> bool {
+    list.split(',')
+        .map(str::trim)
+        .any(|candidate| {
+            if candidate == "*" {
+                return true;
+            }
+            match candidate.strip_prefix("W/") {
+                Some(candidate) => weak && candidate == etag,
+                None => candidate == etag,
+            }
+        })
+}
+
+// A Range is only honoured when If-Range still names the current representation.
+fn if_range_matches(request: &Request, etag: &str, last_modified: Option<&str>) -> bool {
+    match request.headers.get_str("If-Range") {
+        None => true,
+        Some(value) if value.starts_with('"') => value == etag,
+        Some(value) => Some(value) == last_modified,
+    }
+}
+
+enum RangeRequest {
+    Full,
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+// Only a single byte range is served; anything else falls back to the
+// full representation, which RFC 9110 allows.
+fn parse_range(header: &str, length: u64) -> RangeRequest {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) =>
+            match suffix.parse::<u64>() {
+                Ok(0) => {
+                    return RangeRequest::Unsatisfiable;
+                }
+                Ok(suffix) => (length.saturating_sub(suffix), length.saturating_sub(1)),
+                Err(_) => {
+                    return RangeRequest::Full;
+                }
+            }
+        (start, "") =>
+            match start.parse::<u64>() {
+                Ok(start) => (start, length.saturating_sub(1)),
+                Err(_) => {
+                    return RangeRequest::Full;
+                }
+            }
+        (start, end) =>
+            match (start.parse::<u64>(), end.parse::<u64>()) {
+                (Ok(start), Ok(end)) if start <= end => (start, end.min(length.saturating_sub(1))),
+                _ => {
+                    return RangeRequest::Full;
+                }
+            }
+    };
+
+    if length == 0 || start >= length {
+        RangeRequest::Unsatisfiable
+    } else {
+        RangeRequest::Partial(start, end)
+    }
+}
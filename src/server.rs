@@ -1,23 +1,42 @@
-use std::collections::HashMap;
 use std::net::{ IpAddr, Ipv4Addr, SocketAddr };
 use std::pin::Pin;
 use std::future::Future;
-use tokio::io::{ AsyncReadExt, AsyncWriteExt };
-use tokio::net::{ TcpListener, TcpStream };
-use tokio::sync::oneshot;
+use std::sync::Arc;
+use tokio::io::{ AsyncRead, AsyncWrite, AsyncWriteExt };
+use tokio::net::TcpListener;
+use tokio::sync::{ oneshot, watch };
+use tokio::time::timeout;
+use tokio_rustls::{ rustls, TlsAcceptor };
 use log::{ info, error };
 
+use crate::config::config::ServerConfig;
+use crate::http2;
+use crate::http_core::headers::{ HeaderName, HeaderValue };
+use crate::http_core::http_types::HttpMethods;
+use crate::http_core::reader::RequestReader;
+use crate::http_core::upgrade::{ Rewind, Upgraded };
 use crate::http_core::request::{ HttpRequest, Request };
 use crate::http_core::response::Response;
+use crate::router::handler::BoxedHandler;
+use crate::router::middleware::{ Middleware, Next };
+use crate::router::router::{ default_error_response, Router };
+use crate::tls::Certificates;
 
 #[derive(Debug)]
 pub struct CrabServer {
     pub addr: SocketAddr,
+    routers: Vec<Router>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    config: ServerConfig,
+    certificates: Arc<Certificates>,
 }
 
 #[allow(async_fn_in_trait)]
 pub trait Server {
     fn new(ip: [u8; 4], port: u16) -> Self;
+    fn add_router(self, router: Router) -> Self;
+    fn add_middleware<M: Middleware>(self, middleware: M) -> Self;
+    fn with_config(self, config: ServerConfig) -> Self;
     async fn run(
         &self,
         database_connection: Option<Pin<Box<dyn Future<Output = ()> + Send + 'static>>>,
@@ -29,7 +48,31 @@ pub trait Server {
 impl Server for CrabServer {
     fn new(ip: [u8; 4], port: u16) -> Self {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip)), port);
-        CrabServer { addr }
+        CrabServer {
+            addr,
+            routers: Vec::new(),
+            middleware: Vec::new(),
+            config: ServerConfig::default(),
+            certificates: Arc::new(Certificates::new()),
+        }
+    }
+
+    fn add_router(mut self, router: Router) -> Self {
+        if let Some(identity) = router.tls_identity() {
+            self.certificates.add(identity);
+        }
+        self.routers.push(router);
+        self
+    }
+
+    fn add_middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    fn with_config(mut self, config: ServerConfig) -> Self {
+        self.config = config;
+        self
     }
 
     async fn run(
@@ -39,6 +82,30 @@ impl Server for CrabServer {
         shutdown_signal: Option<oneshot::Receiver<()>>
     ) -> Result<(), Box<dyn std::error::Error>> {
         let listener = TcpListener::bind(&self.addr).await?;
+        let routers = Arc::new(self.routers.clone());
+        let endpoint: BoxedHandler = Arc::new(move |request| {
+            let routers = routers.clone();
+            Box::pin(async move { dispatch(&routers, request).await })
+        });
+        let app = Next::new(self.middleware.clone().into(), endpoint);
+        let config = Arc::new(self.config.clone());
+
+        // Any router with a certificate switches the whole listener to TLS.
+        let tls = if self.certificates.is_empty() {
+            None
+        } else {
+            self.certificates.reload()?;
+            let mut tls_config = rustls::ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_cert_resolver(self.certificates.clone());
+            tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+            Some(TlsAcceptor::from(Arc::new(tls_config)))
+        };
+        let reloader = match (&tls, self.config.tls_reload_interval) {
+            (Some(_), Some(interval)) => Some(tokio::spawn(watch_certificates(self.certificates.clone(), interval))),
+            _ => None,
+        };
 
         if let Some(db_connection) = database_connection {
             db_connection.await;
@@ -49,79 +116,238 @@ impl Server for CrabServer {
         on_listen(&self.addr);
         info!("Server listening on {}", self.addr);
 
-        match shutdown_signal {
+        // Open HTTP/2 connections watch this to send GOAWAY when the server stops.
+        let (stopping, stop_signal) = watch::channel(false);
+        let result = match shutdown_signal {
             Some(shutdown) => {
                 tokio::select! {
-                    res = accept_connections(listener) => {
+                    res = accept_connections(listener, tls, app, config, stop_signal) => {
                         if let Err(e) = res {
                             error!("Error accepting connections: {}", e);
                         }
                     },
                     _ = shutdown => {
                         info!("Shutdown signal received, stopping server.");
+                        let _ = stopping.send(true);
                     }
                 }
+                Ok(())
             }
-            None => {
-                accept_connections(listener).await?;
-            }
+            None => accept_connections(listener, tls, app, config, stop_signal).await,
+        };
+
+        if let Some(reloader) = reloader {
+            reloader.abort();
         }
+        result
+    }
+}
 
-        Ok(())
+impl CrabServer {
+    /// The certificates served over TLS; call `reload` on them to pick up
+    /// renewed files without restarting.
+    pub fn certificates(&self) -> Arc<Certificates> {
+        self.certificates.clone()
     }
 }
 
-async fn accept_connections(listener: TcpListener) -> Result<(), Box<dyn std::error::Error>> {
+async fn watch_certificates(certificates: Arc<Certificates>, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
     loop {
-        let (socket, _) = listener.accept().await?;
+        ticker.tick().await;
+        match certificates.reload_if_changed() {
+            Ok(true) => info!("Reloaded TLS certificates"),
+            Ok(false) => {}
+            Err(e) => error!("Failed to reload TLS certificates: {}", e),
+        }
+    }
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    app: Next,
+    config: Arc<ServerConfig>,
+    stop_signal: watch::Receiver<bool>
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        let tls = tls.clone();
+        let app = app.clone();
+        let config = config.clone();
+        let stop_signal = stop_signal.clone();
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(socket).await {
+            // The handshake runs in the connection's task so a slow client can't stall accept().
+            // HTTP/2 is chosen by ALPN over TLS and by the connection preface in cleartext.
+            let result = match tls {
+                Some(acceptor) =>
+                    match timeout(config.tls_handshake_timeout, acceptor.accept(socket)).await {
+                        Ok(Ok(stream)) if stream.get_ref().1.alpn_protocol() == Some(b"h2") =>
+                            http2::serve_connection(stream, app, config, stop_signal).await,
+                        Ok(Ok(stream)) => handle_connection(stream, app, config).await,
+                        Ok(Err(e)) => Err(e.into()),
+                        Err(_) => Err("TLS handshake timed out".into()),
+                    }
+                None =>
+                    match timeout(config.keep_alive_timeout, http2::sniff_preface(&mut socket)).await {
+                        Ok(Ok((true, preface))) =>
+                            http2::serve_connection(Rewind::new(preface, socket), app, config, stop_signal).await,
+                        Ok(Ok((false, read))) => handle_connection(Rewind::new(read, socket), app, config).await,
+                        Ok(Err(e)) => Err(e.into()),
+                        Err(_) => Ok(()),
+                    }
+            };
+            if let Err(e) = result {
                 error!("Failed to handle connection: {}", e);
             }
         });
     }
 }
 
-async fn handle_connection(mut socket: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
-    let mut buffer = [0; 1024];
-
-    match socket.read(&mut buffer).await {
-        Ok(_) => {
-            let response_str = String::from_utf8(buffer.to_vec());
-            match Request::parse(&response_str.unwrap()).await {
-                Ok(request) => {
-                    let response = match request.path.as_str() {
-                        "/" =>
-                            Response {
-                                status_code: 200,
-                                headers: HashMap::new(),
-                                body: b"Welcome to CrabServer".to_vec(),
-                            },
-                        "/hello" =>
-                            Response {
-                                status_code: 200,
-                                headers: HashMap::new(),
-                                body: b"Welcome to CrabServer Other path, so the router is working".to_vec(),
-                            },
-                        _ =>
-                            Response {
-                                status_code: 404,
-                                headers: HashMap::new(),
-                                body: b"Not Found".to_vec(),
-                            },
-                    };
-                    let formated_response = response.format().unwrap();
-                    socket.write_all(&formated_response).await?;
+async fn handle_connection<S: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
+    socket: S,
+    app: Next,
+    config: Arc<ServerConfig>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (read_half, mut write_half) = tokio::io::split(socket);
+    let mut reader = RequestReader::new(read_half, &config);
+    let mut served = 0;
+
+    // Requests are answered strictly one after another, which keeps the
+    // responses to pipelined requests in the order they were received.
+    loop {
+        // The reader applies the keep-alive timeout until a request starts
+        // and the read timeout while its head and body arrive.
+        let request = reader.read_request().await;
+
+        let mut chunked_allowed = true;
+        let mut head_only = false;
+        let (mut response, mut keep_alive) = match request {
+            Ok(Some(request)) => {
+                served += 1;
+                let keep_alive = wants_keep_alive(&request);
+                let version = request.version.clone();
+                chunked_allowed = version != "HTTP/1.0";
+                head_only = request.method == HttpMethods::HEAD;
+                let (mut response, body_read) = respond_while_reading(&mut reader, app.clone().run(request)).await;
+                let keep_alive =
+                    keep_alive &&
+                    body_read &&
+                    served < config.max_requests_per_connection &&
+                    !response.headers.contains_token("Connection", "close");
+
+                if keep_alive && version == "HTTP/1.0" {
+                    response.headers.insert(HeaderName::from_static("Connection"), HeaderValue::from_static("keep-alive"));
                 }
-                Err(e) => {
-                    eprintln!("Failed to parse request: {:?}", e);
+                (response, keep_alive)
+            }
+            Ok(None) => {
+                return Ok(());
+            }
+            Err(e) =>
+                match e.status_code() {
+                    Some(status_code) => {
+                        error!("Rejecting request: {}", e);
+                        (Response::new(status_code).add_body(e.to_string().into_bytes()), false)
+                    }
+                    None => {
+                        return Err(e.into());
+                    }
                 }
+        };
+
+        // The handler takes the connection over, and HTTP/1.1 ends here.
+        if response.status_code == 101 {
+            if let Some(upgrade) = response.upgrade().cloned() {
+                response.write_head_to(&mut write_half).await?;
+                let (read_half, buffered) = reader.into_inner();
+                upgrade.run(Upgraded::new(buffered, read_half.unsplit(write_half))).await;
+                return Ok(());
             }
         }
-        Err(e) => {
-            error!("Failed to read from socket; err = {:?}", e);
+
+        // A streamed body of unknown length is chunked, or ended by closing
+        // the connection when the client can't take chunked encoding.
+        if response.body.is_stream() && !response.has_header("Content-Length") {
+            if !chunked_allowed {
+                keep_alive = false;
+            } else if !response.is_chunked() {
+                response = response.chunked();
+            }
+        }
+        if !keep_alive {
+            response.headers.insert(HeaderName::from_static("Connection"), HeaderValue::from_static("close"));
+        }
+        if response.is_chunked() && !chunked_allowed {
+            response.headers.remove("Transfer-Encoding");
+        }
+
+        if head_only {
+            response.write_head_to(&mut write_half).await?;
+        } else {
+            response.write_to(&mut write_half).await?;
+        }
+
+        if !keep_alive {
+            // Over TLS this sends close_notify, so clients can tell the end from a truncation.
+            write_half.shutdown().await?;
+            return Ok(());
+        }
+    }
+}
+
+// A body left streaming to the handler is read alongside it. Returns whether
+// it was read to the end; if not, the connection can't carry another request.
+async fn respond_while_reading<R: AsyncRead + Unpin>(
+    reader: &mut RequestReader<R>,
+    response: impl Future<Output = Response>
+) -> (Response, bool) {
+    if !reader.has_streamed_body() {
+        return (response.await, true);
+    }
+
+    let body = reader.read_streamed_body();
+    tokio::pin!(body, response);
+    let mut body_read = None;
+    loop {
+        tokio::select! {
+            response = &mut response => {
+                return (response, body_read == Some(true));
+            }
+            result = &mut body, if body_read.is_none() => {
+                body_read = Some(result.is_ok());
+            }
         }
     }
+}
+
+fn wants_keep_alive(request: &Request) -> bool {
+    if request.version == "HTTP/1.0" {
+        request.headers.contains_token("Connection", "keep-alive")
+    } else {
+        !request.headers.contains_token("Connection", "close")
+    }
+}
 
-    Ok(())
+// Routers whose prefix matches are tried longest prefix first, and the first
+// with a route for the path answers. If none has one, the longest answers
+// with its 404. Among equal prefixes the last registered goes first.
+async fn dispatch(routers: &[Router], request: Request) -> Response {
+    let mut candidates: Vec<&Router> = routers
+        .iter()
+        .rev()
+        .filter(|router| router.matches(request.raw_path()))
+        .collect();
+    candidates.sort_by_key(|router| std::cmp::Reverse(router.path().len()));
+
+    let router = candidates
+        .iter()
+        .find(|router| router.has_route(request.raw_path()))
+        .or(candidates.first());
+
+    match router {
+        Some(router) => router.handle(request).await,
+        None => default_error_response(404),
+    }
 }
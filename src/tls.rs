@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::{ Arc, Mutex, RwLock };
+use std::time::SystemTime;
+use rustls_pemfile::Item;
+use thiserror::Error;
+use tokio_rustls::rustls::{
+    server::{ ClientHello, ResolvesServerCert },
+    sign::{ self, CertifiedKey },
+    Certificate,
+    PrivateKey,
+};
+use webpki::EndEntityCert;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("Failed to read {0}: {1}")] IoError(String, std::io::Error),
+    #[error("No certificate found in {0}")] MissingCertificateError(String),
+    #[error("No private key found in {0}")] MissingPrivateKeyError(String),
+    #[error("Unsupported private key in {0}")] InvalidPrivateKeyError(String),
+}
+
+/// A certificate chain and its private key, both PEM encoded.
+#[derive(Clone)]
+pub enum TlsIdentity {
+    Files {
+        certificate: PathBuf,
+        private_key: PathBuf,
+    },
+    Pem {
+        certificate: Arc<[u8]>,
+        private_key: Arc<[u8]>,
+    },
+}
+
+// Key material is redacted, since routers and servers end up in debug logs.
+impl fmt::Debug for TlsIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsIdentity::Files { certificate, private_key } =>
+                f.debug_struct("Files")
+                    .field("certificate", certificate)
+                    .field("private_key", private_key)
+                    .finish(),
+            TlsIdentity::Pem { certificate, .. } =>
+                f.debug_struct("Pem")
+                    .field("certificate", &format_args!("<{} bytes>", certificate.len()))
+                    .field("private_key", &format_args!("<redacted>"))
+                    .finish(),
+        }
+    }
+}
+
+impl TlsIdentity {
+    fn load(&self) -> Result<CertifiedKey, TlsError> {
+        let (certificate, private_key, origin) = match self {
+            TlsIdentity::Files { certificate, private_key } => {
+                let read = |path: &PathBuf| {
+                    std::fs::read(path).map_err(|e| TlsError::IoError(path.display().to_string(), e))
+                };
+                (read(certificate)?, read(private_key)?, certificate.display().to_string())
+            }
+            TlsIdentity::Pem { certificate, private_key } =>
+                (certificate.to_vec(), private_key.to_vec(), "in-memory PEM".to_string()),
+        };
+
+        let chain = rustls_pemfile::certs(&mut BufReader::new(certificate.as_slice()))
+            .map_err(|e| TlsError::IoError(origin.clone(), e))?;
+        if chain.is_empty() {
+            return Err(TlsError::MissingCertificateError(origin));
+        }
+
+        let mut reader = BufReader::new(private_key.as_slice());
+        let key = loop {
+            match rustls_pemfile::read_one(&mut reader).map_err(|e| TlsError::IoError(origin.clone(), e))? {
+                Some(Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key)) => {
+                    break key;
+                }
+                Some(_) => {}
+                None => {
+                    return Err(TlsError::MissingPrivateKeyError(origin));
+                }
+            }
+        };
+        let key = sign::any_supported_type(&PrivateKey(key))
+            .map_err(|_| TlsError::InvalidPrivateKeyError(origin))?;
+
+        Ok(CertifiedKey::new(chain.into_iter().map(Certificate).collect(), key))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        match self {
+            TlsIdentity::Files { certificate, private_key } => {
+                let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+                modified(certificate).max(modified(private_key))
+            }
+            TlsIdentity::Pem { .. } => None,
+        }
+    }
+}
+
+/// The certificates a server presents, picked per connection from the SNI
+/// name. The first identity is the default for clients that send no name or
+/// one no certificate covers.
+///
+/// `reload` swaps in freshly loaded certificates; handshakes already under
+/// way keep the ones they started with.
+#[derive(Default)]
+pub struct Certificates {
+    identities: RwLock<Vec<TlsIdentity>>,
+    loaded: RwLock<Loaded>,
+    modified: Mutex<Vec<Option<SystemTime>>>,
+}
+
+/// Loaded keys, indexed by the DNS names their certificates cover so a
+/// handshake only does a map lookup.
+#[derive(Default)]
+struct Loaded {
+    default: Option<Arc<CertifiedKey>>,
+    /// Lowercase names, wildcards kept as `*.example.com`.
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+    count: usize,
+}
+
+impl Loaded {
+    fn new(keys: Vec<Arc<CertifiedKey>>) -> Self {
+        let mut by_name = HashMap::new();
+        for key in &keys {
+            let end_entity = key.cert
+                .first()
+                .and_then(|end_entity| EndEntityCert::try_from(end_entity.0.as_slice()).ok());
+            let names = end_entity.as_ref().and_then(|end_entity| end_entity.dns_names().ok());
+            for name in names.into_iter().flatten() {
+                // The first identity covering a name wins.
+                let name: &str = name.into();
+                by_name.entry(name.to_ascii_lowercase()).or_insert_with(|| key.clone());
+            }
+        }
+        Self { default: keys.first().cloned(), by_name, count: keys.len() }
+    }
+
+    fn select(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let matching = server_name.map(str::to_ascii_lowercase).and_then(|name| {
+            self.by_name.get(&name).or_else(|| {
+                // A wildcard covers exactly one leading label.
+                let (_, parent) = name.split_once('.')?;
+                self.by_name.get(&format!("*.{}", parent))
+            })
+        });
+        matching.or(self.default.as_ref()).cloned()
+    }
+}
+
+impl Certificates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, identity: TlsIdentity) {
+        self.identities.write().unwrap().push(identity);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.identities.read().unwrap().is_empty()
+    }
+
+    /// Loads every identity again. On error the certificates in use are kept.
+    pub fn reload(&self) -> Result<(), TlsError> {
+        let identities = self.identities.read().unwrap().clone();
+        let modified = identities.iter().map(TlsIdentity::modified).collect();
+        let loaded = identities
+            .iter()
+            .map(|identity| identity.load().map(Arc::new))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        *self.loaded.write().unwrap() = Loaded::new(loaded);
+        *self.modified.lock().unwrap() = modified;
+        Ok(())
+    }
+
+    /// Reloads when a certificate or key file changed on disk since the last load.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let modified: Vec<_> = self.identities.read().unwrap().iter().map(TlsIdentity::modified).collect();
+        if *self.modified.lock().unwrap() == modified {
+            return Ok(false);
+        }
+        self.reload().map(|_| true)
+    }
+}
+
+impl ResolvesServerCert for Certificates {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.loaded.read().unwrap().select(client_hello.server_name())
+    }
+}
+
+impl fmt::Debug for Certificates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certificates")
+            .field("identities", &self.identities.read().unwrap())
+            .field("loaded", &self.loaded.read().unwrap().count)
+            .finish()
+    }
+}
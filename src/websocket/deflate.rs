@@ -0,0 +1,158 @@
+use flate2::{ Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status };
+
+use super::frame::Role;
+use super::socket::WebSocketError;
+
+/// Trailer every message ends with after a sync flush; it is left off the
+/// wire (RFC 7692 section 7.2.1).
+const SYNC_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Agreed parameters of the permessage-deflate extension (RFC 7692).
+///
+/// Only the full 32 KiB window is supported, so offers that ask the server
+/// to use a smaller one are declined.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PerMessageDeflate {
+    pub server_no_context_takeover: bool,
+    pub client_no_context_takeover: bool,
+}
+
+impl PerMessageDeflate {
+    /// The first acceptable offer in a `Sec-WebSocket-Extensions` value,
+    /// or the extension a server agreed to.
+    pub fn parse(header: &str) -> Option<Self> {
+        header.split(',').find_map(|offer| {
+            let mut params = offer.split(';').map(str::trim);
+            if !params.next()?.eq_ignore_ascii_case("permessage-deflate") {
+                return None;
+            }
+
+            let mut agreed = Self::default();
+            let mut seen = Vec::new();
+            for param in params {
+                let (name, value) = match param.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (param, None),
+                };
+                let name = name.to_ascii_lowercase();
+                if seen.contains(&name) {
+                    return None;
+                }
+                match (name.as_str(), value) {
+                    ("server_no_context_takeover", None) => {
+                        agreed.server_no_context_takeover = true;
+                    }
+                    ("client_no_context_takeover", None) => {
+                        agreed.client_no_context_takeover = true;
+                    }
+                    ("server_max_window_bits", Some("15")) => {}
+                    ("client_max_window_bits", None) => {}
+                    ("client_max_window_bits", Some(bits)) if
+                        bits.parse::<u8>().is_ok_and(|bits| (8..=15).contains(&bits))
+                    => {}
+                    _ => {
+                        return None;
+                    }
+                }
+                seen.push(name);
+            }
+            Some(agreed)
+        })
+    }
+
+    /// The `Sec-WebSocket-Extensions` value that confirms these parameters.
+    pub fn to_header(&self) -> String {
+        let mut header = "permessage-deflate".to_string();
+        if self.server_no_context_takeover {
+            header.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            header.push_str("; client_no_context_takeover");
+        }
+        header
+    }
+}
+
+/// Compression state for one connection.
+pub(crate) struct Deflate {
+    compress: Compress,
+    decompress: Decompress,
+    reset_compress: bool,
+    reset_decompress: bool,
+}
+
+impl Deflate {
+    pub(crate) fn new(params: &PerMessageDeflate, role: Role, level: u32) -> Self {
+        let (ours, theirs) = match role {
+            Role::Server => (params.server_no_context_takeover, params.client_no_context_takeover),
+            Role::Client => (params.client_no_context_takeover, params.server_no_context_takeover),
+        };
+        Self {
+            compress: Compress::new(Compression::new(level), false),
+            decompress: Decompress::new(false),
+            reset_compress: ours,
+            reset_decompress: theirs,
+        }
+    }
+
+    pub(crate) fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, WebSocketError> {
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            if output.len() == output.capacity() {
+                output.reserve(output.capacity().max(64));
+            }
+            self.compress
+                .compress_vec(&data[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(|e| WebSocketError::CompressionError(e.to_string()))?;
+            // The flush is complete once all input is taken and output space is left over.
+            if (self.compress.total_in() - start) as usize == data.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+
+        if output.ends_with(&SYNC_TRAILER) {
+            output.truncate(output.len() - SYNC_TRAILER.len());
+        }
+        if self.reset_compress {
+            self.compress.reset();
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn decompress(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, WebSocketError> {
+        let input = [data, &SYNC_TRAILER].concat();
+        let mut output = Vec::with_capacity((data.len() * 2).clamp(64, max_size.max(64)));
+        let start = self.decompress.total_in();
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            if output.len() == output.capacity() {
+                if output.len() > max_size {
+                    return Err(WebSocketError::MessageTooLargeError(max_size));
+                }
+                output.reserve(output.capacity());
+            }
+            let written = output.len();
+            let status = self.decompress
+                .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|e| WebSocketError::CompressionError(e.to_string()))?;
+
+            let consumed_now = (self.decompress.total_in() - start) as usize;
+            if status == Status::StreamEnd || (consumed_now == input.len() && output.len() < output.capacity()) {
+                break;
+            }
+            if consumed_now == consumed && output.len() == written && output.len() < output.capacity() {
+                return Err(WebSocketError::CompressionError("Truncated deflate stream".to_string()));
+            }
+        }
+
+        if output.len() > max_size {
+            return Err(WebSocketError::MessageTooLargeError(max_size));
+        }
+        if self.reset_decompress {
+            self.decompress.reset(false);
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{ BuildHasher, Hasher };
+use bytes::{ Buf, BufMut, BytesMut };
+use tokio_util::codec::{ Decoder, Encoder };
+
+use super::socket::WebSocketError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl OpCode {
+    pub fn is_control(&self) -> bool {
+        matches!(self, OpCode::Close | OpCode::Ping | OpCode::Pong)
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x0 => Some(OpCode::Continuation),
+            0x1 => Some(OpCode::Text),
+            0x2 => Some(OpCode::Binary),
+            0x8 => Some(OpCode::Close),
+            0x9 => Some(OpCode::Ping),
+            0xa => Some(OpCode::Pong),
+            _ => None,
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            OpCode::Continuation => 0x0,
+            OpCode::Text => 0x1,
+            OpCode::Binary => 0x2,
+            OpCode::Close => 0x8,
+            OpCode::Ping => 0x9,
+            OpCode::Pong => 0xa,
+        }
+    }
+}
+
+/// A single frame, already unmasked. `rsv1` marks a compressed message
+/// when permessage-deflate is in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub fin: bool,
+    pub rsv1: bool,
+    pub opcode: OpCode,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(opcode: OpCode, payload: Vec<u8>) -> Self {
+        Self { fin: true, rsv1: false, opcode, payload }
+    }
+}
+
+/// Which end of the connection a codec or socket serves. Clients mask what
+/// they send and servers require it (RFC 6455 section 5.3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Server,
+    Client,
+}
+
+/// Encodes and decodes frames as laid out in RFC 6455 section 5.2.
+#[derive(Debug, Clone)]
+pub struct FrameCodec {
+    role: Role,
+    max_frame_size: usize,
+    masks: RandomState,
+    sent: u64,
+}
+
+impl FrameCodec {
+    pub fn new(role: Role, max_frame_size: usize) -> Self {
+        Self { role, max_frame_size, masks: RandomState::new(), sent: 0 }
+    }
+
+    // Masking keys only have to be unpredictable to scripts in the client,
+    // which never see them, so a keyed hash of a counter is enough.
+    fn next_mask(&mut self) -> [u8; 4] {
+        let mut hasher = self.masks.build_hasher();
+        hasher.write_u64(self.sent);
+        self.sent += 1;
+        (hasher.finish() as u32).to_be_bytes()
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = WebSocketError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, WebSocketError> {
+        if src.len() < 2 {
+            return Ok(None);
+        }
+        let (first, second) = (src[0], src[1]);
+        let masked = second & 0x80 != 0;
+        let length_size = match second & 0x7f {
+            126 => 2,
+            127 => 8,
+            _ => 0,
+        };
+        let header_size = 2 + length_size + if masked { 4 } else { 0 };
+        if src.len() < header_size {
+            return Ok(None);
+        }
+
+        let fin = first & 0x80 != 0;
+        let rsv1 = first & 0x40 != 0;
+        if first & 0x30 != 0 {
+            return Err(WebSocketError::ProtocolError("Reserved bits set without an extension"));
+        }
+        let opcode = OpCode::from_u8(first & 0x0f).ok_or(WebSocketError::ProtocolError("Unknown opcode"))?;
+
+        let length = match length_size {
+            2 => u16::from_be_bytes([src[2], src[3]]) as u64,
+            8 => u64::from_be_bytes(src[2..10].try_into().unwrap()),
+            _ => (second & 0x7f) as u64,
+        };
+        if opcode.is_control() && (!fin || length > 125) {
+            return Err(WebSocketError::ProtocolError("Control frames must be unfragmented and at most 125 bytes"));
+        }
+        if !opcode.is_control() && length > self.max_frame_size as u64 {
+            return Err(WebSocketError::FrameTooLargeError(self.max_frame_size));
+        }
+        if masked != (self.role == Role::Server) {
+            return Err(
+                WebSocketError::ProtocolError(
+                    if masked { "Server frames must not be masked" } else { "Client frames must be masked" }
+                )
+            );
+        }
+
+        let length = length as usize;
+        if src.len() < header_size + length {
+            src.reserve(header_size + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(header_size - if masked { 4 } else { 0 });
+        let mask = if masked {
+            let mask = [src[0], src[1], src[2], src[3]];
+            src.advance(4);
+            Some(mask)
+        } else {
+            None
+        };
+        let mut payload = src.split_to(length).to_vec();
+        if let Some(mask) = mask {
+            apply_mask(&mut payload, mask);
+        }
+
+        Ok(Some(Frame { fin, rsv1, opcode, payload }))
+    }
+}
+
+impl Encoder<Frame> for FrameCodec {
+    type Error = WebSocketError;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), WebSocketError> {
+        let mask = if self.role == Role::Client { Some(self.next_mask()) } else { None };
+        let length = frame.payload.len();
+        dst.reserve(14 + length);
+
+        dst.put_u8(((frame.fin as u8) << 7) | ((frame.rsv1 as u8) << 6) | frame.opcode.as_u8());
+        let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+        if length < 126 {
+            dst.put_u8(mask_bit | (length as u8));
+        } else if length <= u16::MAX as usize {
+            dst.put_u8(mask_bit | 126);
+            dst.put_u16(length as u16);
+        } else {
+            dst.put_u8(mask_bit | 127);
+            dst.put_u64(length as u64);
+        }
+
+        match mask {
+            Some(mask) => {
+                dst.put_slice(&mask);
+                let mut payload = frame.payload;
+                apply_mask(&mut payload, mask);
+                dst.put_slice(&payload);
+            }
+            None => dst.put_slice(&frame.payload),
+        }
+        Ok(())
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
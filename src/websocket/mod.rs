@@ -0,0 +1,4 @@
+pub mod frame;
+pub mod deflate;
+pub mod socket;
+pub mod upgrade;
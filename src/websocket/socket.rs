@@ -0,0 +1,457 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{ ready, Context, Poll };
+use futures_util::{ Sink, SinkExt, Stream, StreamExt };
+use thiserror::Error;
+use tokio::io::{ AsyncRead, AsyncWrite };
+use tokio_util::codec::Framed;
+
+use crate::http_core::upgrade::Upgraded;
+use super::deflate::{ Deflate, PerMessageDeflate };
+use super::frame::{ Frame, FrameCodec, OpCode, Role };
+
+#[derive(Debug, Error)]
+pub enum WebSocketError {
+    #[error("WebSocket protocol error: {0}")] ProtocolError(&'static str),
+    #[error("Frame exceeds the size limit of {0} bytes")] FrameTooLargeError(usize),
+    #[error("Message exceeds the size limit of {0} bytes")] MessageTooLargeError(usize),
+    #[error("Text message is not valid UTF-8")]
+    InvalidUtf8Error,
+    #[error("Failed to inflate or deflate message: {0}")] CompressionError(String),
+    #[error("WebSocket is closed")]
+    ConnectionClosedError,
+    #[error("Failed to read from or write to socket: {0}")] IoError(#[from] std::io::Error),
+}
+
+impl WebSocketError {
+    /// Close code to send the peer, or `None` when the connection is unusable.
+    pub fn close_code(&self) -> Option<CloseCode> {
+        match self {
+            WebSocketError::ProtocolError(_) => Some(CloseCode::PROTOCOL_ERROR),
+            WebSocketError::FrameTooLargeError(_) | WebSocketError::MessageTooLargeError(_) =>
+                Some(CloseCode::MESSAGE_TOO_BIG),
+            WebSocketError::InvalidUtf8Error | WebSocketError::CompressionError(_) =>
+                Some(CloseCode::INVALID_PAYLOAD),
+            WebSocketError::ConnectionClosedError | WebSocketError::IoError(_) => None,
+        }
+    }
+}
+
+/// Status code of a close frame (RFC 6455 section 7.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloseCode(pub u16);
+
+impl CloseCode {
+    pub const NORMAL: CloseCode = CloseCode(1000);
+    pub const GOING_AWAY: CloseCode = CloseCode(1001);
+    pub const PROTOCOL_ERROR: CloseCode = CloseCode(1002);
+    pub const UNSUPPORTED_DATA: CloseCode = CloseCode(1003);
+    pub const INVALID_PAYLOAD: CloseCode = CloseCode(1007);
+    pub const POLICY_VIOLATION: CloseCode = CloseCode(1008);
+    pub const MESSAGE_TOO_BIG: CloseCode = CloseCode(1009);
+    pub const INTERNAL_ERROR: CloseCode = CloseCode(1011);
+
+    /// Whether the code may appear in a close frame; 1005, 1006 and 1015
+    /// only describe a closure locally.
+    pub fn is_sendable(&self) -> bool {
+        matches!(self.0, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: CloseCode,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    /// Answered with a pong automatically; handlers only see it.
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(Option<CloseFrame>),
+}
+
+impl Message {
+    pub fn text(text: impl Into<String>) -> Self {
+        Message::Text(text.into())
+    }
+
+    pub fn close(code: CloseCode, reason: &str) -> Self {
+        Message::Close(Some(CloseFrame { code, reason: reason.to_string() }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    pub max_frame_size: usize,
+    pub max_message_size: usize,
+    pub permessage_deflate: bool,
+    pub compression_level: u32,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_size: 1024 * 1024,
+            max_message_size: 4 * 1024 * 1024,
+            permessage_deflate: false,
+            compression_level: 6,
+        }
+    }
+}
+
+impl WebSocketConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest data frame accepted; outgoing messages are fragmented to fit it.
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Largest message accepted after reassembly and decompression.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Accepts the permessage-deflate extension when a client offers it.
+    pub fn permessage_deflate(mut self, permessage_deflate: bool) -> Self {
+        self.permessage_deflate = permessage_deflate;
+        self
+    }
+
+    pub fn compression_level(mut self, compression_level: u32) -> Self {
+        self.compression_level = compression_level.min(9);
+        self
+    }
+}
+
+pub type WebSocketSender<S = Upgraded> = futures_util::stream::SplitSink<WebSocket<S>, Message>;
+pub type WebSocketReceiver<S = Upgraded> = futures_util::stream::SplitStream<WebSocket<S>>;
+
+/// A WebSocket connection as a `Stream` of received messages and a `Sink`
+/// of messages to send.
+///
+/// Fragmented messages are reassembled, pings are answered, and a close
+/// frame from the peer is echoed before the stream ends. Protocol errors
+/// close the connection with the matching code.
+pub struct WebSocket<S = Upgraded> {
+    framed: Framed<S, FrameCodec>,
+    role: Role,
+    config: WebSocketConfig,
+    deflate: Option<Deflate>,
+    // Opcode, payload so far and whether it is compressed.
+    fragments: Option<(OpCode, Vec<u8>, bool)>,
+    // Control frames the socket answers on its own.
+    pending: VecDeque<Frame>,
+    close_sent: bool,
+    close_received: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    pub fn new(io: S, role: Role, config: WebSocketConfig) -> Self {
+        Self {
+            framed: Framed::new(io, FrameCodec::new(role, config.max_frame_size)),
+            role,
+            config,
+            deflate: None,
+            fragments: None,
+            pending: VecDeque::new(),
+            close_sent: false,
+            close_received: false,
+        }
+    }
+
+    /// Compresses and decompresses messages as agreed in the handshake.
+    pub fn with_permessage_deflate(mut self, params: &PerMessageDeflate) -> Self {
+        self.deflate = Some(Deflate::new(params, self.role, self.config.compression_level));
+        self
+    }
+
+    /// Splits into halves that can be used from different tasks.
+    pub fn split(self) -> (WebSocketSender<S>, WebSocketReceiver<S>) {
+        StreamExt::split(self)
+    }
+
+    /// Receives the next message, or `None` once the connection is closed.
+    pub async fn recv(&mut self) -> Option<Result<Message, WebSocketError>> {
+        self.next().await
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), WebSocketError> {
+        SinkExt::send(self, message).await
+    }
+
+    fn on_frame(&mut self, frame: Frame) -> Result<Option<Message>, WebSocketError> {
+        if frame.opcode.is_control() && frame.rsv1 {
+            return Err(WebSocketError::ProtocolError("Control frames cannot be compressed"));
+        }
+
+        match frame.opcode {
+            OpCode::Ping => {
+                if !self.close_sent {
+                    self.pending.push_back(Frame::new(OpCode::Pong, frame.payload.clone()));
+                }
+                Ok(Some(Message::Ping(frame.payload)))
+            }
+            OpCode::Pong => Ok(Some(Message::Pong(frame.payload))),
+            OpCode::Close => {
+                let close = parse_close(&frame.payload)?;
+                self.close_received = true;
+                if !self.close_sent {
+                    let payload = match &close {
+                        Some(close) => close.code.0.to_be_bytes().to_vec(),
+                        None => Vec::new(),
+                    };
+                    self.pending.push_back(Frame::new(OpCode::Close, payload));
+                    self.close_sent = true;
+                }
+                Ok(Some(Message::Close(close)))
+            }
+            OpCode::Text | OpCode::Binary => {
+                if self.fragments.is_some() {
+                    return Err(WebSocketError::ProtocolError("New message started before the last one ended"));
+                }
+                if frame.rsv1 && self.deflate.is_none() {
+                    return Err(WebSocketError::ProtocolError("Compressed message without permessage-deflate"));
+                }
+                if frame.fin {
+                    return self.finish_message(frame.opcode, frame.payload, frame.rsv1).map(Some);
+                }
+                self.check_message_size(frame.payload.len())?;
+                self.fragments = Some((frame.opcode, frame.payload, frame.rsv1));
+                Ok(None)
+            }
+            OpCode::Continuation => {
+                if frame.rsv1 {
+                    return Err(WebSocketError::ProtocolError("Continuation frames cannot set RSV1"));
+                }
+                let Some((opcode, mut payload, compressed)) = self.fragments.take() else {
+                    return Err(WebSocketError::ProtocolError("Continuation frame without a message"));
+                };
+                self.check_message_size(payload.len() + frame.payload.len())?;
+                payload.extend_from_slice(&frame.payload);
+                if frame.fin {
+                    return self.finish_message(opcode, payload, compressed).map(Some);
+                }
+                self.fragments = Some((opcode, payload, compressed));
+                Ok(None)
+            }
+        }
+    }
+
+    fn check_message_size(&self, size: usize) -> Result<(), WebSocketError> {
+        if size > self.config.max_message_size {
+            return Err(WebSocketError::MessageTooLargeError(self.config.max_message_size));
+        }
+        Ok(())
+    }
+
+    fn finish_message(&mut self, opcode: OpCode, payload: Vec<u8>, compressed: bool) -> Result<Message, WebSocketError> {
+        let payload = match (&mut self.deflate, compressed) {
+            (Some(deflate), true) => deflate.decompress(&payload, self.config.max_message_size)?,
+            _ => payload,
+        };
+        match opcode {
+            OpCode::Text => String::from_utf8(payload).map(Message::Text).map_err(|_| WebSocketError::InvalidUtf8Error),
+            _ => Ok(Message::Binary(payload)),
+        }
+    }
+
+    fn encode_message(&mut self, message: Message) -> Result<Vec<Frame>, WebSocketError> {
+        let (opcode, payload) = match message {
+            Message::Text(text) => (OpCode::Text, text.into_bytes()),
+            Message::Binary(data) => (OpCode::Binary, data),
+            Message::Ping(data) => (OpCode::Ping, data),
+            Message::Pong(data) => (OpCode::Pong, data),
+            Message::Close(close) => {
+                self.close_sent = true;
+                let payload = match close {
+                    Some(close) => {
+                        if !close.code.is_sendable() {
+                            return Err(WebSocketError::ProtocolError("Close code cannot be sent"));
+                        }
+                        [&close.code.0.to_be_bytes()[..], close.reason.as_bytes()].concat()
+                    }
+                    None => Vec::new(),
+                };
+                (OpCode::Close, payload)
+            }
+        };
+
+        if opcode.is_control() {
+            if payload.len() > 125 {
+                return Err(WebSocketError::ProtocolError("Control frame payload exceeds 125 bytes"));
+            }
+            return Ok(vec![Frame::new(opcode, payload)]);
+        }
+
+        let (payload, compressed) = match &mut self.deflate {
+            Some(deflate) => (deflate.compress(&payload)?, true),
+            None => (payload, false),
+        };
+        let mut frames: Vec<Frame> = payload
+            .chunks(self.config.max_frame_size.max(1))
+            .map(|chunk| Frame { fin: false, rsv1: false, opcode: OpCode::Continuation, payload: chunk.to_vec() })
+            .collect();
+        if frames.is_empty() {
+            frames.push(Frame::new(OpCode::Continuation, Vec::new()));
+        }
+        frames[0].opcode = opcode;
+        frames[0].rsv1 = compressed;
+        frames.last_mut().unwrap().fin = true;
+        Ok(frames)
+    }
+
+    // Hands queued pongs and close replies to the codec.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        while let Some(frame) = self.pending.pop_front() {
+            match self.framed.poll_ready_unpin(cx) {
+                Poll::Ready(Ok(())) => self.framed.start_send_unpin(frame)?,
+                Poll::Ready(Err(e)) => {
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => {
+                    self.pending.push_front(frame);
+                    return Poll::Pending;
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn parse_close(payload: &[u8]) -> Result<Option<CloseFrame>, WebSocketError> {
+    match payload {
+        [] => Ok(None),
+        [_] => Err(WebSocketError::ProtocolError("Close frame with a one-byte payload")),
+        [high, low, reason @ ..] => {
+            let code = CloseCode(u16::from_be_bytes([*high, *low]));
+            if !code.is_sendable() {
+                return Err(WebSocketError::ProtocolError("Invalid close code"));
+            }
+            let reason = String::from_utf8(reason.to_vec()).map_err(|_| WebSocketError::InvalidUtf8Error)?;
+            Ok(Some(CloseFrame { code, reason }))
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Stream for WebSocket<S> {
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            this.poll_replies(cx);
+
+            // The server ends the TCP connection once both close frames are out.
+            if this.close_received {
+                if !this.pending.is_empty() {
+                    return Poll::Pending;
+                }
+                if this.role == Role::Server {
+                    let _ = ready!(this.framed.poll_close_unpin(cx));
+                }
+                return Poll::Ready(None);
+            }
+
+            let frame = match ready!(this.framed.poll_next_unpin(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(e)) => {
+                    let e = this.fail(e);
+                    this.poll_replies(cx);
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    return Poll::Ready(None);
+                }
+            };
+            match this.on_frame(frame) {
+                Ok(Some(message)) => {
+                    return Poll::Ready(Some(Ok(message)));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let e = this.fail(e);
+                    this.poll_replies(cx);
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    // Replies go out as part of reading. A write that can't finish yet is
+    // retried on the next poll and must not hold back reads.
+    fn poll_replies(&mut self, cx: &mut Context<'_>) {
+        if self.pending.is_empty() {
+            return;
+        }
+        match self.poll_pending(cx) {
+            Poll::Ready(Ok(())) => {
+                let _ = self.framed.poll_flush_unpin(cx);
+            }
+            Poll::Ready(Err(_)) => {
+                self.pending.clear();
+                self.close_received = true;
+            }
+            Poll::Pending => {}
+        }
+    }
+
+    // Queues the close frame an error calls for and stops reading.
+    fn fail(&mut self, error: WebSocketError) -> WebSocketError {
+        if let (Some(code), false) = (error.close_code(), self.close_sent) {
+            self.pending.push_back(Frame::new(OpCode::Close, code.0.to_be_bytes().to_vec()));
+            self.close_sent = true;
+        }
+        self.close_received = true;
+        error
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Sink<Message> for WebSocket<S> {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        this.framed.poll_ready_unpin(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: Message) -> Result<(), WebSocketError> {
+        let this = self.get_mut();
+        if this.close_sent {
+            return Err(WebSocketError::ConnectionClosedError);
+        }
+        for frame in this.encode_message(message)? {
+            this.framed.start_send_unpin(frame)?;
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        this.framed.poll_flush_unpin(cx)
+    }
+
+    /// Sends a close frame unless one went out already, then shuts the
+    /// writing side down.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        let this = self.get_mut();
+        if !this.close_sent {
+            this.pending.push_back(Frame::new(OpCode::Close, CloseCode::NORMAL.0.to_be_bytes().to_vec()));
+            this.close_sent = true;
+        }
+        ready!(this.poll_pending(cx))?;
+        this.framed.poll_close_unpin(cx)
+    }
+}
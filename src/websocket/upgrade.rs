@@ -0,0 +1,125 @@
+use std::future::Future;
+use async_trait::async_trait;
+use base64::{ engine::general_purpose::STANDARD, Engine };
+use sha1::{ Digest, Sha1 };
+
+use crate::http_core::{ request::Request, response::Response };
+use crate::router::handler::FromRequest;
+use super::deflate::PerMessageDeflate;
+use super::frame::Role;
+use super::socket::{ WebSocket, WebSocketConfig, WebSocketReceiver, WebSocketSender };
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(ACCEPT_GUID.as_bytes());
+    STANDARD.encode(hasher.finalize())
+}
+
+/// A validated `Upgrade: websocket` request. Rejects with 400, or 426 for
+/// an unsupported `Sec-WebSocket-Version`.
+///
+/// Limits and permessage-deflate come from a `WebSocketConfig` registered
+/// with `Router::with_state`, or the defaults.
+#[derive(Debug)]
+pub struct WebSocketUpgrade {
+    key: String,
+    offered_protocols: Vec<String>,
+    protocol: Option<String>,
+    deflate: Option<PerMessageDeflate>,
+    config: WebSocketConfig,
+}
+
+#[async_trait]
+impl FromRequest for WebSocketUpgrade {
+    async fn from_request(request: &Request) -> Result<Self, Response> {
+        let bad_request = |message: &str| Response::new(400).add_body(message.as_bytes().to_vec());
+
+        if !request.headers.contains_token("Connection", "upgrade") || !request.headers.contains_token("Upgrade", "websocket") {
+            return Err(bad_request("Expected Connection: Upgrade and Upgrade: websocket"));
+        }
+        if request.headers.get_str("Sec-WebSocket-Version").map(str::trim) != Some("13") {
+            return Err(
+                Response::new(426)
+                    .add_header("Sec-WebSocket-Version", "13")
+                    .add_body(b"Unsupported Sec-WebSocket-Version".to_vec())
+            );
+        }
+        let key = request.headers.get_str("Sec-WebSocket-Key").map(str::trim).unwrap_or_default();
+        if STANDARD.decode(key).map(|nonce| nonce.len()) != Ok(16) {
+            return Err(bad_request("Invalid Sec-WebSocket-Key"));
+        }
+
+        let offered_protocols = request.headers
+            .get_all("Sec-WebSocket-Protocol")
+            .flat_map(|value| value.as_str().split(','))
+            .map(|protocol| protocol.trim().to_string())
+            .filter(|protocol| !protocol.is_empty())
+            .collect();
+        let config = request.extensions.get::<WebSocketConfig>().cloned().unwrap_or_default();
+        let deflate = if config.permessage_deflate {
+            let extensions: Vec<&str> = request.headers
+                .get_all("Sec-WebSocket-Extensions")
+                .map(|value| value.as_str())
+                .collect();
+            PerMessageDeflate::parse(&extensions.join(","))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            key: key.to_string(),
+            offered_protocols,
+            protocol: None,
+            deflate,
+            config,
+        })
+    }
+}
+
+impl WebSocketUpgrade {
+    /// Picks the first subprotocol the client offered that is in `supported`.
+    pub fn protocols(mut self, supported: &[&str]) -> Self {
+        self.protocol = self.offered_protocols
+            .iter()
+            .find(|offered| supported.contains(&offered.as_str()))
+            .cloned();
+        self
+    }
+
+    /// The subprotocol the connection will use, if any.
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Answers with `101 Switching Protocols` and runs `callback` on the
+    /// connection once the response is sent.
+    pub fn on_upgrade<F, Fut>(self, callback: F) -> Response
+        where
+            F: FnOnce(WebSocketSender, WebSocketReceiver) -> Fut + Send + 'static,
+            Fut: Future<Output = ()> + Send + 'static
+    {
+        let mut response = Response::new(101)
+            .add_header("Upgrade", "websocket")
+            .add_header("Connection", "Upgrade")
+            .add_header("Sec-WebSocket-Accept", &accept_key(&self.key));
+        if let Some(protocol) = &self.protocol {
+            response = response.add_header("Sec-WebSocket-Protocol", protocol);
+        }
+        if let Some(deflate) = &self.deflate {
+            response = response.add_header("Sec-WebSocket-Extensions", &deflate.to_header());
+        }
+
+        response.on_upgrade(move |upgraded| async move {
+            let mut socket = WebSocket::new(upgraded, Role::Server, self.config);
+            if let Some(deflate) = &self.deflate {
+                socket = socket.with_permessage_deflate(deflate);
+            }
+            let (sender, receiver) = socket.split();
+            callback(sender, receiver).await;
+        })
+    }
+}
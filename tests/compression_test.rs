@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{ Read, Write };
+    use flate2::{ read::{ GzDecoder, ZlibDecoder }, write::GzEncoder };
+    use CrabServe::{
+        http_core::{
+            http_types::{ ContentType, HttpMethods },
+            request::{ HttpRequest, Request },
+            response::Response,
+        },
+        router::{ compression::Compression, router::Router },
+    };
+
+    fn page() -> String {
+        "<p>Crabs are decapod crustaceans.</p>".repeat(20)
+    }
+
+    fn router() -> Router {
+        Router::new("/".to_string())
+            .add_middleware(Compression::new().min_size(64))
+            .add_route(HttpMethods::GET, "/page", || async {
+                Response::new(200).set_content_type(ContentType::TextHtml).add_body(page().into_bytes())
+            })
+            .add_route(HttpMethods::GET, "/tiny", || async { "tiny" })
+            .add_route(HttpMethods::GET, "/photo", || async {
+                Response::new(200).set_content_type(ContentType::ImagePng).add_body(vec![0; 4096])
+            })
+            .add_route(HttpMethods::POST, "/echo", |request: Request| async move {
+                String::from_utf8_lossy(request.get_body()).into_owned()
+            })
+    }
+
+    async fn get(path: &str, accept_encoding: Option<&str>) -> Response {
+        let mut request = Request::new("GET", path);
+        if let Some(accept_encoding) = accept_encoding {
+            request.add_header("Accept-Encoding", accept_encoding);
+        }
+        router().handle(request).await
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_encoding() {
+        let response = get("/page", Some("gzip, deflate, br")).await;
+        assert_eq!(response.headers().get_str("Content-Encoding"), Some("br"));
+        assert_eq!(response.headers().get_str("Vary"), Some("Accept-Encoding"));
+        let mut decoded = String::new();
+        brotli::Decompressor::new(response.body(), 4096).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, page());
+
+        let response = get("/page", Some("br;q=0.5, gzip;q=0.8")).await;
+        assert_eq!(response.headers().get_str("Content-Encoding"), Some("gzip"));
+        let mut decoded = String::new();
+        GzDecoder::new(response.body()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, page());
+        assert!(response.body().len() < page().len());
+
+        let response = get("/page", Some("deflate")).await;
+        assert_eq!(response.headers().get_str("Content-Encoding"), Some("deflate"));
+        let mut decoded = String::new();
+        ZlibDecoder::new(response.body()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, page());
+    }
+
+    #[tokio::test]
+    async fn test_empty_accept_encoding_means_identity() {
+        for accept_encoding in ["", " , "] {
+            let response = get("/page", Some(accept_encoding)).await;
+            assert!(response.headers().get_str("Content-Encoding").is_none());
+            assert_eq!(response.body(), page().as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skips_compression() {
+        let response = get("/page", None).await;
+        assert!(!response.has_header("Content-Encoding"));
+        assert_eq!(response.body(), page().as_bytes());
+
+        let response = get("/page", Some("identity, gzip;q=0")).await;
+        assert!(!response.has_header("Content-Encoding"));
+
+        let response = get("/tiny", Some("gzip")).await;
+        assert!(!response.has_header("Content-Encoding"));
+        assert_eq!(response.body(), b"tiny");
+
+        let response = get("/photo", Some("gzip")).await;
+        assert!(!response.has_header("Content-Encoding"));
+        assert!(!response.has_header("Vary"));
+    }
+
+    #[tokio::test]
+    async fn test_decodes_gzip_request_bodies() {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"compressed upload").unwrap();
+
+        let mut request = Request::new("POST", "/echo");
+        request.add_header("Content-Encoding", "gzip");
+        request.add_body(encoder.finish().unwrap());
+        let response = router().handle(request).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.body(), b"compressed upload");
+
+        let mut request = Request::new("POST", "/echo");
+        request.add_header("Content-Encoding", "gzip");
+        request.add_body(b"not gzip".to_vec());
+        assert_eq!(router().handle(request).await.status_code(), 400);
+
+        let mut request = Request::new("POST", "/echo");
+        request.add_header("Content-Encoding", "zstd");
+        assert_eq!(router().handle(request).await.status_code(), 415);
+    }
+
+    #[tokio::test]
+    async fn test_limits_decompressed_size() {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[b'a'; 10_000]).unwrap();
+
+        let router = Router::new("/".to_string())
+            .add_middleware(Compression::new().max_decompressed_size(1024))
+            .add_route(HttpMethods::POST, "/echo", || async { "ok" });
+        let mut request = Request::new("POST", "/echo");
+        request.add_header("Content-Encoding", "gzip");
+        request.add_body(encoder.finish().unwrap());
+        assert_eq!(router.handle(request).await.status_code(), 413);
+    }
+}
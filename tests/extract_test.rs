@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use serde::{ Deserialize, Serialize };
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request } },
+        router::{ extract::{ Form, Json, Query }, handler::{ FromRequest, IntoResponse }, router::Router },
+    };
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+        page: u32,
+    }
+
+    #[tokio::test]
+    async fn test_query_extractor() {
+        let request = Request::parse("GET /search?q=crab%20cake&page=2 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let Query(search) = Query::<Search>::from_request(&request).await.unwrap();
+        assert_eq!(search, Search { q: "crab cake".to_string(), page: 2 });
+
+        let request = Request::parse("GET /search?q=crab&page=two HTTP/1.1\r\n\r\n").await.unwrap();
+        let rejection = Query::<Search>::from_request(&request).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 400);
+        assert!(String::from_utf8_lossy(rejection.body()).contains("Failed to deserialize query string"));
+    }
+
+    #[tokio::test]
+    async fn test_routes_match_without_query() {
+        let router = Router::new("/".to_string()).add_route(
+            HttpMethods::GET,
+            "/search",
+            |Query(search): Query<Search>| async move { format!("{} on page {}", search.q, search.page) }
+        );
+
+        let request = Request::parse("GET /search?q=crab&page=3 HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = router.handle(request).await;
+
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.body(), b"crab on page 3");
+    }
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    fn json_request(content_type: &str, body: &str) -> Request {
+        let mut request = Request::new("POST", "/users");
+        request.add_header("Content-Type", content_type);
+        request.add_body(body.as_bytes().to_vec());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_json_extractor() {
+        let request = json_request("application/json; charset=UTF-8", "{ \"name\": \"Ferris\", \"age\": 8 }");
+        let Json(user) = Json::<User>::from_request(&request).await.unwrap();
+        assert_eq!(user, User { name: "Ferris".to_string(), age: 8 });
+
+        let request = json_request("application/vnd.api+json", "{\"name\":\"Ferris\",\"age\":8}");
+        assert!(Json::<User>::from_request(&request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_json_extractor_rejections() {
+        let request = json_request("text/plain", "{\"name\":\"Ferris\",\"age\":8}");
+        let rejection = Json::<User>::from_request(&request).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 415);
+
+        let request = json_request("application/json; charset=latin1", "{\"name\":\"Ferris\",\"age\":8}");
+        let rejection = Json::<User>::from_request(&request).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 415);
+
+        let request = json_request("application/json", "{\"name\":\"Ferris\",");
+        let rejection = Json::<User>::from_request(&request).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 400);
+        let error: serde_json::Value = serde_json::from_slice(rejection.body()).unwrap();
+        assert_eq!(error["error"], "invalid_json_syntax");
+        assert_eq!(error["line"], 1);
+
+        let request = json_request("application/json", "{\"name\":\"Ferris\",\"age\":\"eight\"}");
+        let rejection = Json::<User>::from_request(&request).await.unwrap_err();
+        let error: serde_json::Value = serde_json::from_slice(rejection.body()).unwrap();
+        assert_eq!(error["error"], "invalid_json_data");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_reaches_the_handler_untouched() {
+        let raw = "POST /users HTTP/1.1\r\nContent-Type: application/json\r\n\r\n{not json";
+        let request = Request::parse(raw).await.unwrap();
+        assert_eq!(request.get_body(), b"{not json");
+
+        let response = Json(User { name: "Ferris".to_string(), age: 8 }).into_response();
+        assert_eq!(response.headers().get_str("Content-Type"), Some("application/json"));
+        assert_eq!(response.body(), b"{\"name\":\"Ferris\",\"age\":8}");
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Address {
+        city: String,
+        zip: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Signup {
+        name: String,
+        roles: Vec<String>,
+        address: Address,
+        #[serde(default)]
+        newsletter: Option<bool>,
+    }
+
+    fn form_request(body: &str) -> Request {
+        let mut request = Request::new("POST", "/admin/users");
+        request.add_header("Content-Type", "application/x-www-form-urlencoded");
+        request.add_body(body.as_bytes().to_vec());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_form_extractor() {
+        let body = "name=Ferris+the%20Crab&roles=admin&roles=editor&address%5Bcity%5D=Sofia&address%5Bzip%5D=1000";
+        let Form(signup) = Form::<Signup>::from_request(&form_request(body)).await.unwrap();
+        assert_eq!(signup, Signup {
+            name: "Ferris the Crab".to_string(),
+            roles: vec!["admin".to_string(), "editor".to_string()],
+            address: Address { city: "Sofia".to_string(), zip: 1000 },
+            newsletter: None,
+        });
+
+        let body = "name=Ferris&roles[]=admin&address[city]=Sofia&address[zip]=1000&newsletter=true";
+        let Form(signup) = Form::<Signup>::from_request(&form_request(body)).await.unwrap();
+        assert_eq!(signup.roles, vec!["admin".to_string()]);
+        assert_eq!(signup.newsletter, Some(true));
+
+        // A single plain value still fills a sequence field.
+        let body = "name=Ferris&roles=admin&address[city]=Sofia&address[zip]=1000";
+        let Form(signup) = Form::<Signup>::from_request(&form_request(body)).await.unwrap();
+        assert_eq!(signup.roles, vec!["admin".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_form_extractor_rejections() {
+        let rejection = Form::<Signup>::from_request(&form_request("name=Ferris")).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 400);
+
+        // Malformed bodies are rejected rather than read as an empty form.
+        for body in ["name=Ferris&roles=admin&address[city=Sofia&address[zip]=1000", "name=Ferris&name[first]=Crab"] {
+            let rejection = Form::<Signup>::from_request(&form_request(body)).await.unwrap_err();
+            assert_eq!(rejection.status_code(), 400);
+        }
+
+        let request = json_request("application/json", "{\"name\":\"Ferris\"}");
+        let rejection = Form::<Signup>::from_request(&request).await.unwrap_err();
+        assert_eq!(rejection.status_code(), 415);
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use CrabServe::http_core::{
+        headers::{ HeaderMap, HeaderName, HeaderValue, InvalidHeader },
+        request::{ HttpRequest, Request },
+        response::Response,
+    };
+
+    #[test]
+    fn test_lookup_is_case_insensitive() {
+        let mut headers = HeaderMap::new();
+        headers.try_insert("Content-Type", "text/html").unwrap();
+
+        assert_eq!(headers.get("content-type").unwrap(), "text/html");
+        assert_eq!(headers.get_str("CONTENT-TYPE"), Some("text/html"));
+        assert!(headers.contains_key("Content-type"));
+
+        headers.try_insert("content-type", "application/json").unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get_str("Content-Type"), Some("application/json"));
+    }
+
+    #[test]
+    fn test_repeated_headers_keep_order() {
+        let mut headers = HeaderMap::new();
+        headers.try_append("Set-Cookie", "a=1").unwrap();
+        headers.try_append("Accept", "text/html").unwrap();
+        headers.try_append("set-cookie", "b=2").unwrap();
+
+        let cookies: Vec<&str> = headers.get_all("Set-Cookie").map(HeaderValue::as_str).collect();
+        assert_eq!(cookies, vec!["a=1", "b=2"]);
+
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Set-Cookie", "Accept", "set-cookie"]);
+
+        assert_eq!(headers.remove("SET-COOKIE").unwrap(), "a=1");
+        assert!(!headers.contains_key("Set-Cookie"));
+    }
+
+    #[test]
+    fn test_rejects_header_injection() {
+        assert!(matches!(
+            HeaderValue::try_from("value\r\nSet-Cookie: evil=1"),
+            Err(InvalidHeader::InvalidHeaderValueError(_))
+        ));
+        assert!(matches!(
+            HeaderName::try_from("Bad Name"),
+            Err(InvalidHeader::InvalidHeaderNameError(_))
+        ));
+        assert!(HeaderName::try_from("X-Custom_Header").is_ok());
+
+        let response = Response::new(200).try_add_header("Location", "/\r\nX-Injected: 1");
+        assert!(response.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_and_response_use_header_map() {
+        let raw_request =
+            "GET / HTTP/1.1\r\ncontent-type: text/plain\r\nAccept: text/html\r\nAccept: application/json\r\n\r\n";
+        let request = Request::parse(raw_request).await.unwrap();
+
+        assert_eq!(request.get_headers().get_str("Content-Type"), Some("text/plain"));
+        assert_eq!(request.get_headers().get_all("accept").count(), 2);
+
+        let response = Response::new(200)
+            .append_header("Set-Cookie", "a=1")
+            .append_header("Set-Cookie", "b=2");
+        let formatted = String::from_utf8(response.format().unwrap()).unwrap();
+        assert!(formatted.contains("Set-Cookie: a=1\r\nSet-Cookie: b=2\r\n"));
+    }
+}
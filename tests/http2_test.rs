@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use bytes::Bytes;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream, sync::oneshot };
+    use tokio_rustls::{ rustls, TlsConnector };
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request } },
+        router::router::Router,
+        server::{ CrabServer, Server },
+    };
+
+    fn router() -> Router {
+        Router::new("/".to_string())
+            .add_route(HttpMethods::GET, "/hello", |request: Request| async move {
+                format!("hello {} over {}", request.query, request.version)
+            })
+            .add_route(HttpMethods::POST, "/echo", |request: Request| async move {
+                String::from_utf8_lossy(request.get_body()).into_owned()
+            })
+            .add_route(HttpMethods::GET, "/files/:name", |request: Request| async move {
+                format!("{} query={:?}", request.param("name").unwrap_or_default(), request.query)
+            })
+    }
+
+    async fn read_body(response: http::Response<h2::RecvStream>) -> (u16, String) {
+        let status = response.status().as_u16();
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            let _ = body.flow_control().release_capacity(chunk.len());
+            bytes.extend_from_slice(&chunk);
+        }
+        (status, String::from_utf8(bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_h2c_prior_knowledge() {
+        let (shutdown, shutdown_signal) = oneshot::channel();
+        let server = CrabServer::new([127, 0, 0, 1], 3090).add_router(router());
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, Some(shutdown_signal)).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let socket = TcpStream::connect("127.0.0.1:3090").await.unwrap();
+        let (client, connection) = h2::client::handshake(socket).await.unwrap();
+        let connection = tokio::spawn(connection);
+        let mut client = client.ready().await.unwrap();
+
+        // Both streams are open before either response is read.
+        let first = http::Request::get("http://127.0.0.1:3090/hello?n=1").body(()).unwrap();
+        let (first, _) = client.send_request(first, true).unwrap();
+        let second = http::Request::post("http://127.0.0.1:3090/echo").body(()).unwrap();
+        let (second, mut upload) = client.send_request(second, false).unwrap();
+        upload.send_data(Bytes::from_static(b"multiplexed "), false).unwrap();
+        upload.send_data(Bytes::from_static(b"upload"), true).unwrap();
+
+        let (status, body) = read_body(second.await.unwrap()).await;
+        assert_eq!(status, 200);
+        assert_eq!(body, "multiplexed upload");
+
+        let response = first.await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+        assert!(response.headers().get("connection").is_none());
+        assert_eq!(read_body(response).await, (200, "hello n=1 over HTTP/2.0".to_string()));
+
+        let head = http::Request::head("http://127.0.0.1:3090/hello").body(()).unwrap();
+        let response = client.send_request(head, true).unwrap().0.await.unwrap();
+        assert!(response.body().is_end_stream());
+
+        // An encoded `?` belongs to the path, as over HTTP/1.1.
+        let encoded = http::Request::get("http://127.0.0.1:3090/files/a%3Fb?n=2").body(()).unwrap();
+        let response = client.send_request(encoded, true).unwrap().0.await.unwrap();
+        assert_eq!(read_body(response).await, (200, "a?b query=\"n=2\"".to_string()));
+
+        // So does an encoded `/`, rather than splitting the segment.
+        let encoded = http::Request::get("http://127.0.0.1:3090/files/a%2Fb").body(()).unwrap();
+        let response = client.send_request(encoded, true).unwrap().0.await.unwrap();
+        assert_eq!(read_body(response).await, (200, "a/b query=\"\"".to_string()));
+
+        let missing = http::Request::get("http://127.0.0.1:3090/missing").body(()).unwrap();
+        let response = client.send_request(missing, true).unwrap().0.await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        // Shutting the server down sends GOAWAY, which ends the client connection cleanly.
+        shutdown.send(()).unwrap();
+        let closed = tokio::time::timeout(tokio::time::Duration::from_secs(2), connection).await;
+        assert!(closed.unwrap().unwrap().is_ok());
+
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http1_still_served_on_cleartext() {
+        let server = CrabServer::new([127, 0, 0, 1], 3091).add_router(router());
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3091").await.unwrap();
+        stream.write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\nConnection: close\r\n\r\nPING").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("PING"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_alpn_over_tls() {
+        let generated = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let certificate = generated.serialize_pem().unwrap();
+        let der = rustls_pemfile::certs(&mut certificate.as_bytes()).unwrap().remove(0);
+
+        let server = CrabServer::new([127, 0, 0, 1], 3092).add_router(
+            router().with_ssl_pem(certificate.as_bytes(), generated.serialize_private_key_pem().as_bytes())
+        );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(&rustls::Certificate(der)).unwrap();
+        let mut config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        let socket = TcpStream::connect("127.0.0.1:3092").await.unwrap();
+        let server_name = rustls::ServerName::try_from("localhost").unwrap();
+        let stream = TlsConnector::from(Arc::new(config)).connect(server_name, socket).await.unwrap();
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+
+        let (client, connection) = h2::client::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+        let mut client = client.ready().await.unwrap();
+        let request = http::Request::get("https://localhost:3092/hello?tls").body(()).unwrap();
+        let response = client.send_request(request, true).unwrap().0.await.unwrap();
+        assert_eq!(read_body(response).await, (200, "hello tls over HTTP/2.0".to_string()));
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use CrabServe::http_core::{
+        http_types::{ ContentType, MediaType, StatusCode },
+        request::{ HttpRequest, Request },
+        response::Response,
+    };
+
+    #[test]
+    fn test_status_code_reason_phrases_and_classes() {
+        assert_eq!(StatusCode::NO_CONTENT.canonical_reason(), Some("No Content"));
+        assert_eq!(StatusCode::from(429).canonical_reason(), Some("Too Many Requests"));
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE.to_string(), "503 Service Unavailable");
+
+        assert!(StatusCode::OK.is_success());
+        assert!(StatusCode::MOVED_PERMANENTLY.is_redirect());
+        assert!(StatusCode::NOT_MODIFIED.is_redirect());
+        assert!(StatusCode::TOO_MANY_REQUESTS.is_client_error());
+        assert!(StatusCode::BAD_GATEWAY.is_server_error());
+        assert!(StatusCode::SWITCHING_PROTOCOLS.is_informational());
+        assert!(!StatusCode::NOT_FOUND.is_success());
+    }
+
+    #[test]
+    fn test_custom_status_codes() {
+        assert_eq!(StatusCode::from_u16(299).unwrap(), 299);
+        assert!(StatusCode::from_u16(99).is_err());
+        assert!(StatusCode::from_u16(1000).is_err());
+
+        let custom = StatusCode::from(299);
+        assert_eq!(custom.canonical_reason(), None);
+        assert!(custom.is_success());
+        assert_eq!(custom.to_string(), "299 Unknown");
+    }
+
+    #[test]
+    fn test_out_of_range_status_codes_are_rejected() {
+        for code in [7, 1234] {
+            // A bug in debug builds, and a 500 in release builds.
+            let status = std::panic::catch_unwind(|| StatusCode::from(code));
+            if cfg!(debug_assertions) {
+                assert!(status.is_err());
+            } else {
+                assert_eq!(status.unwrap(), StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    #[test]
+    fn test_response_uses_status_code() {
+        let response = Response::new(StatusCode::CREATED);
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        assert!(response.format().unwrap().starts_with(b"HTTP/1.1 201 Created\r\n"));
+
+        let response = Response::new(301);
+        assert!(response.status_code().is_redirect());
+        assert!(response.format().unwrap().starts_with(b"HTTP/1.1 301 Moved Permanently\r\n"));
+    }
+
+    #[test]
+    fn test_media_type_parsing() {
+        let media_type: MediaType = "Application/Problem+JSON; Charset=\"UTF-8\"; profile=x".parse().unwrap();
+        assert_eq!(media_type.type_(), "application");
+        assert_eq!(media_type.subtype(), "problem+json");
+        assert_eq!(media_type.suffix(), Some("json"));
+        assert_eq!(media_type.essence(), "application/problem+json");
+        assert_eq!(media_type.charset(), Some("UTF-8"));
+        assert_eq!(media_type.param("PROFILE"), Some("x"));
+        assert!(media_type.is_json());
+        assert!(media_type.matches(&"application/*".parse().unwrap()));
+        assert!(!media_type.matches(&"text/*".parse().unwrap()));
+
+        assert!("json".parse::<MediaType>().is_err());
+        assert!("text/ html".parse::<MediaType>().is_err());
+        assert!(matches!("application/json; charset=utf-8".parse(), Ok(ContentType::ApplicationJson)));
+    }
+
+    #[test]
+    fn test_media_type_display() {
+        let media_type = MediaType::new("multipart", "form-data").with_param("boundary", "a b\"c");
+        assert_eq!(media_type.to_string(), "multipart/form-data; boundary=\"a b\\\"c\"");
+        assert_eq!(media_type.to_string().parse::<MediaType>().unwrap(), media_type);
+
+        assert_eq!(ContentType::ImagePng.to_string(), "image/png");
+        assert_eq!(MediaType::from(ContentType::TextHtml).to_string(), "text/html; charset=utf-8");
+    }
+
+    #[test]
+    fn test_content_type_on_requests_and_responses() {
+        let response = Response::new(200).set_content_type(ContentType::TextPlain);
+        assert_eq!(response.headers().get_str("Content-Type"), Some("text/plain; charset=utf-8"));
+
+        let mut request = Request::new("POST", "/");
+        request.add_header("Content-Type", "application/x-www-form-urlencoded; charset=utf-8");
+        assert!(request.content_type().is_some_and(|media_type| media_type.is_form_urlencoded()));
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        config::config::ServerConfig,
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request } },
+        router::{ handler::IntoResponse, router::Router },
+        server::{ CrabServer, Server },
+    };
+
+    async fn echo_path(request: Request) -> String {
+        format!("path={}", request.path())
+    }
+
+    async fn start_server(port: u16, config: ServerConfig) -> tokio::task::JoinHandle<()> {
+        let server = CrabServer::new([127, 0, 0, 1], port)
+            .with_config(config)
+            .add_router(
+                Router::new("/".to_string())
+                    .add_route(HttpMethods::GET, "/chunked", || async {
+                        "generated".to_string().into_response().chunked()
+                    })
+                    .add_route(HttpMethods::POST, "/upload", |request: Request| async move {
+                        format!("received={}", request.get_body().len())
+                    })
+                    .add_route(HttpMethods::DELETE, "/items", || async { (204, "stray body") })
+                    .add_route(HttpMethods::GET, "/*path", echo_path)
+            );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        server_task
+    }
+
+    async fn read_until(stream: &mut TcpStream, needle: &str) -> String {
+        let mut received = Vec::new();
+        let mut buffer = [0; 1024];
+
+        while !String::from_utf8_lossy(&received).contains(needle) {
+            let n = stream.read(&mut buffer).await.unwrap();
+            assert!(n > 0, "connection closed before {:?} was received", needle);
+            received.extend_from_slice(&buffer[..n]);
+        }
+        String::from_utf8_lossy(&received).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_connection_is_reused_and_pipelined_in_order() {
+        let server_task = start_server(3050, ServerConfig::default()).await;
+        let mut stream = TcpStream::connect("127.0.0.1:3050").await.unwrap();
+
+        stream.write_all(b"GET /first HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/first").await;
+        assert!(response.contains("Content-Length: 11"));
+
+        stream.write_all(b"GET /second HTTP/1.1\r\n\r\nGET /third HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/third").await;
+        let second = response.find("path=/second").unwrap();
+        let third = response.find("path=/third").unwrap();
+        assert!(second < third);
+
+        stream.write_all(b"GET /last HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/last").await;
+        assert!(response.contains("Connection: close"));
+        assert_eq!(stream.read(&mut [0; 16]).await.unwrap(), 0);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_no_content_response_sends_no_body() {
+        let server_task = start_server(3055, ServerConfig::default()).await;
+        let mut stream = TcpStream::connect("127.0.0.1:3055").await.unwrap();
+
+        stream.write_all(b"DELETE /items HTTP/1.1\r\n\r\nGET /after HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/after").await;
+        assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(!response.contains("stray body"));
+
+        // The next response starts right where the 204's head ends.
+        let (head, rest) = response.split_once("\r\n\r\n").unwrap();
+        assert!(!head.contains("Content-Length"));
+        assert!(rest.starts_with("HTTP/1.1 200 OK\r\n"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_1_0_requires_opt_in() {
+        let server_task = start_server(3051, ServerConfig::default()).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3051").await.unwrap();
+        stream.write_all(b"GET /old HTTP/1.0\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.contains("Connection: close"));
+
+        let mut stream = TcpStream::connect("127.0.0.1:3051").await.unwrap();
+        stream.write_all(b"GET /old HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/old").await;
+        assert!(response.contains("Connection: keep-alive"));
+        stream.write_all(b"GET /again HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").await.unwrap();
+        read_until(&mut stream, "path=/again").await;
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_request_limit_and_idle_timeout_close_connection() {
+        let config = ServerConfig::new()
+            .max_requests_per_connection(2)
+            .keep_alive_timeout(Duration::from_millis(200));
+        let server_task = start_server(3052, config).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3052").await.unwrap();
+        stream.write_all(b"GET /one HTTP/1.1\r\n\r\n").await.unwrap();
+        read_until(&mut stream, "path=/one").await;
+        stream.write_all(b"GET /two HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "path=/two").await;
+        assert!(response.contains("Connection: close"));
+        assert_eq!(stream.read(&mut [0; 16]).await.unwrap(), 0);
+
+        let mut stream = TcpStream::connect("127.0.0.1:3052").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(stream.read(&mut [0; 16]).await.unwrap(), 0);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_chunked_responses_keep_connection_usable() {
+        let server_task = start_server(3053, ServerConfig::default()).await;
+        let mut stream = TcpStream::connect("127.0.0.1:3053").await.unwrap();
+
+        stream.write_all(b"GET /chunked HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = read_until(&mut stream, "0\r\n\r\n").await;
+        assert!(response.contains("Transfer-Encoding: chunked"));
+        assert!(!response.contains("Content-Length"));
+        assert!(response.ends_with("\r\n\r\n9\r\ngenerated\r\n0\r\n\r\n"));
+
+        stream.write_all(b"GET /after HTTP/1.1\r\n\r\n").await.unwrap();
+        read_until(&mut stream, "path=/after").await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3053").await.unwrap();
+        stream.write_all(b"GET /chunked HTTP/1.0\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(!response.contains("Transfer-Encoding"));
+        assert!(response.contains("Content-Length: 9"));
+        assert!(response.ends_with("generated"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_slow_upload_outlives_idle_timeout_but_stalled_request_times_out() {
+        let config = ServerConfig::new()
+            .keep_alive_timeout(Duration::from_millis(200))
+            .read_timeout(Duration::from_millis(400));
+        let server_task = start_server(3054, config).await;
+
+        // Each part arrives within the read timeout, though the whole takes longer than the idle timeout.
+        let mut stream = TcpStream::connect("127.0.0.1:3054").await.unwrap();
+        stream.write_all(b"POST /upload HTTP/1.1\r\nContent-Length: 6\r\n\r\n").await.unwrap();
+        for part in [b"ab", b"cd", b"ef"] {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            stream.write_all(part).await.unwrap();
+        }
+        read_until(&mut stream, "received=6").await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3054").await.unwrap();
+        stream.write_all(b"POST /upload HTTP/1.1\r\nContent-Length: 6\r\n\r\nab").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+        assert!(response.contains("Connection: close"));
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        get,
+        post,
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request } },
+        router::{ route::RouteService, router::Router },
+        server::{ CrabServer, Server },
+    };
+
+    #[get("/users/:id")]
+    async fn get_user(request: Request) -> String {
+        format!("User {}", request.param("id").unwrap())
+    }
+
+    #[post("/users")]
+    async fn create_user() -> (u16, &'static str) {
+        (201, "Created")
+    }
+
+    async fn send_request(addr: &str, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut buffer = [0; 1024];
+        let n = stream.read(&mut buffer).await.unwrap();
+        String::from_utf8_lossy(&buffer[..n]).to_string()
+    }
+
+    #[test]
+    fn test_route_service_binds_method_and_path() {
+        assert_eq!(GetUserRoute.method(), HttpMethods::GET);
+        assert_eq!(GetUserRoute.path(), "/users/:id");
+        assert_eq!(CreateUserRoute.method(), HttpMethods::POST);
+        assert_eq!(CreateUserRoute.path(), "/users");
+    }
+
+    #[tokio::test]
+    async fn test_handlers_stay_callable() {
+        let mut request = Request::new("GET", "/users/7");
+        request.params.insert("id".to_string(), "7".to_string());
+        assert_eq!(get_user(request).await, "User 7");
+        assert_eq!(create_user().await, (201, "Created"));
+    }
+
+    #[tokio::test]
+    async fn test_registered_services_are_served() {
+        let server = CrabServer::new([127, 0, 0, 1], 3040).add_router(
+            Router::new("/api".to_string()).add_service(GetUserRoute).add_service(CreateUserRoute)
+        );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let response = send_request("127.0.0.1:3040", "GET /api/users/7 HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 200 OK"));
+        assert!(response.contains("User 7"));
+
+        let response = send_request("127.0.0.1:3040", "POST /api/users HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 201 Created"));
+
+        server_task.abort();
+    }
+
+    // Each file under tests/ui must fail to compile with the error in its `.stderr`.
+    #[test]
+    fn test_invalid_routes_fail_to_compile() {
+        trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+    }
+}
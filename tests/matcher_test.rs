@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use CrabServe::router::{ matcher::{ Matcher, MatcherError }, pattern::PatternError };
+
+    fn params(matcher: &Matcher<&'static str>, path: &str) -> Option<(String, Vec<(String, String)>)> {
+        matcher.at(path).map(|matched| (matched.value.to_string(), matched.params))
+    }
+
+    #[test]
+    fn test_static_and_param_routes() {
+        let mut matcher = Matcher::new();
+        matcher.insert("/users", "users").unwrap();
+        matcher.insert("/users/me", "me").unwrap();
+        matcher.insert("/users/:id", "user").unwrap();
+        matcher.insert("/users/:id/posts/:post_id", "post").unwrap();
+
+        assert_eq!(params(&matcher, "/users"), Some(("users".to_string(), vec![])));
+        assert_eq!(params(&matcher, "/users/me"), Some(("me".to_string(), vec![])));
+        assert_eq!(
+            params(&matcher, "/users/42"),
+            Some(("user".to_string(), vec![("id".to_string(), "42".to_string())]))
+        );
+        assert_eq!(
+            params(&matcher, "/users/42/posts/7"),
+            Some((
+                "post".to_string(),
+                vec![
+                    ("id".to_string(), "42".to_string()),
+                    ("post_id".to_string(), "7".to_string()),
+                ],
+            ))
+        );
+        assert!(matcher.at("/users/42/comments").is_none());
+    }
+
+    #[test]
+    fn test_wildcard_and_optional_routes() {
+        let mut matcher = Matcher::new();
+        matcher.insert("/files/*rest", "files").unwrap();
+        matcher.insert("/posts/:page?", "posts").unwrap();
+
+        assert_eq!(
+            params(&matcher, "/files/images/crab.png"),
+            Some(("files".to_string(), vec![("rest".to_string(), "images/crab.png".to_string())]))
+        );
+        assert!(matcher.at("/files").is_none());
+        assert_eq!(params(&matcher, "/posts"), Some(("posts".to_string(), vec![])));
+        assert_eq!(
+            params(&matcher, "/posts/3"),
+            Some(("posts".to_string(), vec![("page".to_string(), "3".to_string())]))
+        );
+    }
+
+    #[test]
+    fn test_encoded_slashes_stay_in_their_segment() {
+        let mut matcher = Matcher::new();
+        matcher.insert("/files/:dir/:name", "file").unwrap();
+        matcher.insert("/docs/:name", "doc").unwrap();
+        matcher.insert("/assets/*rest", "assets").unwrap();
+
+        assert!(matcher.at("/files/a%2Fb").is_none());
+        assert_eq!(
+            params(&matcher, "/docs/a%2Fb%20c"),
+            Some(("doc".to_string(), vec![("name".to_string(), "a/b c".to_string())]))
+        );
+        assert_eq!(
+            params(&matcher, "/assets/css/site%20v2.css"),
+            Some(("assets".to_string(), vec![("rest".to_string(), "css/site v2.css".to_string())]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let mut matcher = Matcher::new();
+        matcher.insert("/users/:id", "user").unwrap();
+
+        assert_eq!(
+            matcher.insert("users", "user"),
+            Err(MatcherError::InvalidPattern(PatternError::MissingLeadingSlash("users".to_string())))
+        );
+        assert_eq!(
+            matcher.insert("/users/:name", "user"),
+            Err(MatcherError::ConflictingParam("name".to_string(), "id".to_string()))
+        );
+        assert_eq!(
+            matcher.insert("/files/*rest/more", "files"),
+            Err(MatcherError::InvalidPattern(PatternError::WildcardNotLast("*rest".to_string())))
+        );
+        assert_eq!(
+            matcher.insert("/users/:id", "user"),
+            Err(MatcherError::DuplicateRoute("/users/:id".to_string()))
+        );
+        assert_eq!(
+            matcher.insert("/posts/:post-id", "post"),
+            Err(MatcherError::InvalidPattern(PatternError::InvalidParamName(":post-id".to_string())))
+        );
+        assert_eq!(
+            matcher.insert("/search me", "search"),
+            Err(MatcherError::InvalidPattern(PatternError::InvalidCharacter(' ')))
+        );
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request }, response::Response },
+        router::router::Router,
+        server::{ CrabServer, Server },
+    };
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+
+    fn users_router() -> Router {
+        Router::new("/".to_string())
+            .add_route(HttpMethods::GET, "/users", || async { "all users" })
+            .add_route(HttpMethods::POST, "/users", || async { (201, "created") })
+    }
+
+    #[test]
+    fn test_parse_methods() {
+        assert_eq!("GET".parse::<HttpMethods>().unwrap(), HttpMethods::GET);
+        assert_eq!("PURGE".parse::<HttpMethods>().unwrap(), HttpMethods::Extension("PURGE".to_string()));
+        assert_eq!(HttpMethods::Extension("PURGE".to_string()).to_string(), "PURGE");
+        assert!("get".parse::<HttpMethods>().is_ok_and(|method| method != HttpMethods::GET));
+        assert!("GE(T".parse::<HttpMethods>().is_err());
+        assert!("".parse::<HttpMethods>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_method_not_allowed_lists_registered_methods() {
+        let request = Request::parse("DELETE /users HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = users_router().handle(request).await;
+
+        assert_eq!(response.status_code(), 405);
+        assert_eq!(response.headers().get_str("Allow"), Some("GET, POST, HEAD, OPTIONS"));
+    }
+
+    #[tokio::test]
+    async fn test_automatic_head_and_options() {
+        let request = Request::parse("HEAD /users HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = users_router().handle(request).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.body(), b"all users");
+
+        let request = Request::parse("OPTIONS /users HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = users_router().handle(request).await;
+        assert_eq!(response.status_code(), 204);
+        assert_eq!(response.headers().get_str("Allow"), Some("GET, POST, HEAD, OPTIONS"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_methods() {
+        let request = Request::parse("PURGE /users HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = users_router().handle(request).await;
+        assert_eq!(response.status_code(), 501);
+
+        let router = users_router().add_route(
+            HttpMethods::Extension("PURGE".to_string()),
+            "/cache",
+            || async { Response::new(204) }
+        );
+        let request = Request::parse("PURGE /cache HTTP/1.1\r\n\r\n").await.unwrap();
+        assert_eq!(router.handle(request).await.status_code(), 204);
+
+        assert!(Request::parse("G@T /users HTTP/1.1\r\n\r\n").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_head_response_has_no_body() {
+        let server = CrabServer::new([127, 0, 0, 1], 3060).add_router(users_router());
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3060").await.unwrap();
+        stream.write_all(b"HEAD /users HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 9\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let mut stream = TcpStream::connect("127.0.0.1:3060").await.unwrap();
+        stream.write_all(b"G@T /users HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,318 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        config::config::ServerConfig,
+        http_core::{
+            body_stream::BodyReader,
+            http_types::HttpMethods,
+            multipart::{ boundary, Multipart, MultipartConfig, MultipartError, PartData },
+            request::{ HttpRequest, Request },
+        },
+        router::router::Router,
+        server::{ CrabServer, Server },
+    };
+
+    const BODY: &str = concat!(
+        "preamble is ignored\r\n",
+        "--XyZ\r\n",
+        "Content-Disposition: form-data; name=\"title\"\r\n",
+        "\r\n",
+        "Crab photos\r\n",
+        "--XyZ\r\n",
+        "Content-Disposition: form-data; name=\"photo\"; filename=\"crab; \\\"one\\\".png\"\r\n",
+        "Content-Type: image/png\r\n",
+        "\r\n",
+        "\u{1}PNG data with \r\n--Xy inside\r\n",
+        "--XyZ--"
+    );
+
+    fn parser(body: &str, config: MultipartConfig) -> Multipart<Cursor<Vec<u8>>> {
+        Multipart::new(Cursor::new(body.as_bytes().to_vec()), "XyZ", config)
+    }
+
+    #[test]
+    fn test_boundary_parameter() {
+        assert_eq!(boundary("multipart/form-data; boundary=XyZ"), Some("XyZ".to_string()));
+        assert_eq!(boundary("Multipart/Form-Data; charset=utf-8; boundary=\"a b\""), Some("a b".to_string()));
+        assert_eq!(boundary("multipart/form-data"), None);
+        assert_eq!(boundary("application/json; boundary=XyZ"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fields_and_files() {
+        let mut multipart = parser(BODY, MultipartConfig::new());
+
+        let title = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(title.name(), Some("title"));
+        assert!(!title.is_file());
+        assert_eq!(title.text().await.unwrap(), "Crab photos");
+
+        let photo = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(photo.name(), Some("photo"));
+        assert_eq!(photo.filename(), Some("crab; \"one\".png"));
+        assert_eq!(photo.content_type(), Some("image/png"));
+        assert_eq!(photo.bytes().await.unwrap(), b"\x01PNG data with \r\n--Xy inside");
+
+        assert!(multipart.next_part().await.unwrap().is_none());
+        assert!(multipart.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let mut multipart = parser(BODY, MultipartConfig::new().max_part_size(16));
+        assert!(multipart.next_part().await.is_ok());
+        let error = multipart.next_part().await.unwrap_err();
+        assert!(matches!(error, MultipartError::PartTooLargeError(16)));
+        assert_eq!(error.status_code(), 413);
+
+        let mut multipart = parser(BODY, MultipartConfig::new().max_total_size(20));
+        assert!(multipart.next_part().await.is_ok());
+        assert!(matches!(multipart.next_part().await, Err(MultipartError::PayloadTooLargeError(20))));
+
+        let mut multipart = parser(BODY, MultipartConfig::new().max_parts(1));
+        assert!(multipart.next_part().await.is_ok());
+        assert!(matches!(multipart.next_part().await, Err(MultipartError::TooManyPartsError(1))));
+
+        let mut multipart = parser(BODY, MultipartConfig::new().max_part_header_size(32));
+        let error = multipart.next_part().await.unwrap_err();
+        assert!(matches!(error, MultipartError::PartHeadersTooLargeError(32)));
+        assert_eq!(error.status_code(), 431);
+
+        let truncated = &BODY[..BODY.len() - 8];
+        let mut multipart = parser(truncated, MultipartConfig::new());
+        assert!(multipart.next_part().await.is_ok());
+        assert!(matches!(multipart.next_part().await, Err(MultipartError::UnexpectedEofError)));
+    }
+
+    #[tokio::test]
+    async fn test_epilogue_is_consumed() {
+        let body = format!("{}\r\n{}", BODY, "epilogue is ignored too\r\n".repeat(1000));
+        let mut multipart = parser(&body, MultipartConfig::new());
+        assert!(multipart.next_part().await.unwrap().is_some());
+        assert!(multipart.next_part().await.unwrap().is_some());
+        assert!(multipart.next_part().await.unwrap().is_none());
+
+        let mut stream = Cursor::new(body.into_bytes());
+        let mut multipart = Multipart::new(&mut stream, "XyZ", MultipartConfig::new());
+        while multipart.next_part().await.unwrap().is_some() {}
+        assert_eq!(stream.position() as usize, stream.get_ref().len());
+    }
+
+    #[tokio::test]
+    async fn test_large_files_spool_to_disk() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let config = MultipartConfig::new().spool_threshold(8).spool_dir(spool_dir.path());
+        let mut multipart = parser(BODY, config);
+
+        let title = multipart.next_part().await.unwrap().unwrap();
+        assert!(matches!(title.data(), PartData::Memory(_)));
+
+        let photo = multipart.next_part().await.unwrap().unwrap();
+        let PartData::File(file) = photo.data() else {
+            panic!("expected the photo to be spooled");
+        };
+        assert!(file.path().starts_with(spool_dir.path()));
+        assert_eq!(photo.size(), 28);
+
+        let target = spool_dir.path().join("crab.png");
+        photo.save_to(&target).await.unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"\x01PNG data with \r\n--Xy inside");
+    }
+
+    #[tokio::test]
+    async fn test_multipart_extractor() {
+        let router = Router::new("/".to_string()).add_route(
+            HttpMethods::POST,
+            "/upload",
+            |mut multipart: Multipart<BodyReader>| async move {
+                let mut names = Vec::new();
+                while let Some(part) = multipart.next_part().await? {
+                    names.push(format!("{}={}", part.name().unwrap_or_default(), part.size()));
+                }
+                Ok::<_, MultipartError>(names.join(","))
+            }
+        );
+
+        let mut request = Request::new("POST", "/upload");
+        request.add_header("Content-Type", "multipart/form-data; boundary=XyZ");
+        request.add_body(BODY.as_bytes().to_vec());
+        let response = router.handle(request).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.body(), b"title=11,photo=28");
+
+        let mut request = Request::new("POST", "/upload");
+        request.add_header("Content-Type", "multipart/form-data");
+        assert_eq!(router.handle(request).await.status_code(), 415);
+    }
+
+    // Lists each part as `name=size`, marking the ones spooled to disk.
+    fn upload_router(config: MultipartConfig) -> Router {
+        Router::new("/".to_string())
+            .with_state(config)
+            .add_route(HttpMethods::POST, "/upload", |mut multipart: Multipart<BodyReader>| async move {
+                let mut names = Vec::new();
+                while let Some(part) = multipart.next_part().await? {
+                    let spooled = if matches!(part.data(), PartData::File(_)) { "+disk" } else { "" };
+                    names.push(format!("{}={}{}", part.name().unwrap_or_default(), part.size(), spooled));
+                }
+                Ok::<_, MultipartError>(names.join(","))
+            })
+            .add_route(HttpMethods::GET, "/ping", || async { "pong" })
+    }
+
+    fn upload_body(photo_size: usize) -> Vec<u8> {
+        let mut body = b"--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nCrab photos\r\n".to_vec();
+        body.extend_from_slice(b"--XyZ\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"crab.png\"\r\n\r\n");
+        body.extend(std::iter::repeat_n(b'x', photo_size));
+        body.extend_from_slice(b"\r\n--XyZ--");
+        body
+    }
+
+    async fn read_response(stream: &mut TcpStream) -> String {
+        let mut received = Vec::new();
+        let mut buffer = [0; 1024];
+        loop {
+            let text = String::from_utf8_lossy(&received).into_owned();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .map_or(0, |length| length.parse().unwrap());
+                if body.len() >= length {
+                    return text;
+                }
+            }
+            let n = stream.read(&mut buffer).await.unwrap();
+            assert!(n > 0, "connection closed mid-response: {:?}", text);
+            received.extend_from_slice(&buffer[..n]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uploads_stream_past_max_body_size() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let config = MultipartConfig::new()
+            .max_part_size(300_000)
+            .max_total_size(400_000)
+            .spool_threshold(4096)
+            .spool_dir(spool_dir.path());
+        let server = CrabServer::new([127, 0, 0, 1], 3130)
+            .with_config(ServerConfig::new().max_body_size(1024))
+            .add_router(upload_router(config));
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        // Far more than `max_body_size`, which only bounds buffered bodies.
+        let body = upload_body(200_000);
+        let mut stream = TcpStream::connect("127.0.0.1:3130").await.unwrap();
+        let head = format!(
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Type: multipart/form-data; boundary=XyZ\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(&body).await.unwrap();
+        let response = read_response(&mut stream).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with("\r\n\r\ntitle=11,photo=200000+disk"));
+
+        // The body was read to its end, so the connection carries on, chunked bodies included.
+        let mut chunked = String::new();
+        for piece in body.chunks(50_000) {
+            chunked.push_str(&format!("{:x}\r\n{}\r\n", piece.len(), String::from_utf8_lossy(piece)));
+        }
+        let head = "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Type: multipart/form-data; boundary=XyZ\r\nTransfer-Encoding: chunked\r\n\r\n";
+        stream.write_all(format!("{}{}0\r\n\r\n", head, chunked).as_bytes()).await.unwrap();
+        let response = read_response(&mut stream).await;
+        assert!(response.ends_with("\r\n\r\ntitle=11,photo=200000+disk"));
+
+        // A part over the limit is refused without reading the rest, which ends the connection.
+        let body = upload_body(350_000);
+        let head = format!(
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Type: multipart/form-data; boundary=XyZ\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        let _ = stream.write_all(&body).await;
+        let response = read_response(&mut stream).await;
+        assert!(response.starts_with("HTTP/1.1 413 "), "{}", response);
+        assert!(response.contains("Connection: close\r\n"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_browser_uploads_keep_the_connection() {
+        let server = CrabServer::new([127, 0, 0, 1], 3132).add_router(upload_router(MultipartConfig::new()));
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        // Browsers end the closing delimiter with a CRLF, which belongs to the epilogue.
+        let body = upload_body(10_000);
+        let mut stream = TcpStream::connect("127.0.0.1:3132").await.unwrap();
+        let head = format!(
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Type: multipart/form-data; boundary=XyZ\r\nContent-Length: {}\r\n\r\n",
+            body.len() + 2
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(&body).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        stream.write_all(b"\r\n").await.unwrap();
+        let response = read_response(&mut stream).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(!response.contains("Connection: close\r\n"));
+
+        stream.write_all(b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let response = read_response(&mut stream).await;
+        assert!(response.ends_with("\r\n\r\npong"), "{}", response);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_uploads_stream_over_http2() {
+        let server = CrabServer::new([127, 0, 0, 1], 3131)
+            .with_config(ServerConfig::new().max_body_size(1024))
+            .add_router(upload_router(MultipartConfig::new()));
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let socket = TcpStream::connect("127.0.0.1:3131").await.unwrap();
+        let (client, connection) = h2::client::handshake(socket).await.unwrap();
+        tokio::spawn(connection);
+        let mut client = client.ready().await.unwrap();
+
+        let request = http::Request::post("http://127.0.0.1:3131/upload")
+            .header("content-type", "multipart/form-data; boundary=XyZ")
+            .body(())
+            .unwrap();
+        let (response, mut send) = client.send_request(request, false).unwrap();
+        // Sent in pieces, so the server's flow-control window has to open up again.
+        for piece in upload_body(200_000).chunks(16_384) {
+            send.reserve_capacity(piece.len());
+            while send.capacity() < piece.len() {
+                std::future::poll_fn(|cx| send.poll_capacity(cx)).await.unwrap().unwrap();
+            }
+            send.send_data(bytes::Bytes::copy_from_slice(piece), false).unwrap();
+        }
+        send.send_data(bytes::Bytes::new(), true).unwrap();
+
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), 200);
+        let mut body = response.into_body();
+        let mut received = Vec::new();
+        while let Some(chunk) = body.data().await {
+            received.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(received, b"title=11,photo=200000");
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use CrabServe::http_core::{
+        http_types::MediaType,
+        request::{ HttpRequest, Request },
+        response::Response,
+    };
+
+    #[derive(Serialize)]
+    struct Crab {
+        name: &'static str,
+    }
+
+    fn request_with(headers: &[(&str, &str)]) -> Request {
+        let mut request = Request::new("GET", "/crabs/1");
+        for (name, value) in headers {
+            request.headers.try_append(name, value).unwrap();
+        }
+        request
+    }
+
+    fn crab_response(request: &Request) -> Response {
+        Response::negotiate(request)
+            .add_json(&Crab { name: "Ferris" })
+            .add_html("<h1>Ferris</h1>")
+            .add_text("Ferris")
+            .build()
+    }
+
+    #[test]
+    fn test_accept_parsing() {
+        let request = request_with(&[
+            ("Accept", "text/html;level=1, application/json;q=0.5"),
+            ("Accept", "*/*;q=0.1, image/png;q=0"),
+        ]);
+
+        let accept = request.accept();
+        let ranges: Vec<String> = accept.iter().map(|item| item.value.to_string()).collect();
+        assert_eq!(ranges, ["text/html; level=1", "application/json", "*/*", "image/png"]);
+        assert_eq!(accept[1].quality, 0.5);
+        assert_eq!(accept[3].quality, 0.0);
+
+        let available: Vec<MediaType> = vec!["image/png".parse().unwrap(), "text/plain".parse().unwrap()];
+        assert_eq!(request.preferred_media_type(&available), Some(&available[1]));
+    }
+
+    #[test]
+    fn test_language_and_charset() {
+        let request = request_with(&[
+            ("Accept-Language", "bg, en-GB;q=0.8, en;q=0.7, fr;q=0"),
+            ("Accept-Charset", "iso-8859-5, utf-8;q=0.9"),
+        ]);
+
+        assert_eq!(request.preferred_language(&["en-US", "en-GB", "fr"]), Some("en-GB"));
+        assert_eq!(request.preferred_language(&["en-US", "bg-BG"]), Some("bg-BG"));
+        assert_eq!(request.preferred_language(&["fr", "de"]), None);
+        assert_eq!(request.preferred_charset(&["UTF-8", "ISO-8859-5"]), Some("ISO-8859-5"));
+        assert_eq!(request.preferred_charset(&["us-ascii"]), None);
+
+        let request = request_with(&[]);
+        assert_eq!(request.preferred_language(&["en", "bg"]), Some("en"));
+    }
+
+    #[test]
+    fn test_negotiated_response() {
+        let response = crab_response(&request_with(&[("Accept", "text/html,application/xhtml+xml,*/*;q=0.8")]));
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.headers().get_str("Content-Type"), Some("text/html; charset=utf-8"));
+        assert_eq!(response.headers().get_str("Vary"), Some("Accept"));
+        assert_eq!(response.body(), b"<h1>Ferris</h1>");
+
+        let response = crab_response(&request_with(&[("Accept", "application/*")]));
+        assert_eq!(response.headers().get_str("Content-Type"), Some("application/json"));
+        assert_eq!(response.body(), b"{\"name\":\"Ferris\"}");
+
+        let response = crab_response(&request_with(&[]));
+        assert_eq!(response.headers().get_str("Content-Type"), Some("application/json"));
+
+        let response = crab_response(&request_with(&[("Accept", "text/*;q=0.5, text/plain")]));
+        assert_eq!(response.body(), b"Ferris");
+    }
+
+    #[test]
+    fn test_not_acceptable() {
+        let response = crab_response(&request_with(&[("Accept", "image/png, application/json;q=0")]));
+        assert_eq!(response.status_code(), 406);
+        assert!(String::from_utf8_lossy(response.body()).contains("application/json, text/html, text/plain"));
+    }
+}
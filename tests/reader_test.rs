@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use CrabServe::{
+        config::config::ServerConfig,
+        http_core::{ reader::{ ReadError, RequestReader }, request::HttpRequest },
+    };
+
+    #[tokio::test]
+    async fn test_reads_body_larger_than_one_read() {
+        let body = "x".repeat(10_000);
+        let raw = format!("POST /upload HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let (mut client, server) = tokio::io::duplex(64);
+
+        let writer = tokio::spawn(async move {
+            for chunk in raw.as_bytes().chunks(100) {
+                client.write_all(chunk).await.unwrap();
+            }
+            client
+        });
+
+        let mut reader = RequestReader::new(server, &ServerConfig::default());
+        let request = reader.read_request().await.unwrap().unwrap();
+
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.path(), "/upload");
+        assert_eq!(request.get_body(), body.as_bytes());
+
+        drop(writer.await.unwrap());
+        assert!(reader.read_request().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keeps_bytes_of_following_request() {
+        let raw: &[u8] =
+            b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcGET /b HTTP/1.1\r\n\r\n";
+        let mut reader = RequestReader::new(raw, &ServerConfig::default());
+
+        let first = reader.read_request().await.unwrap().unwrap();
+        assert_eq!(first.get_body(), b"abc");
+
+        let second = reader.read_request().await.unwrap().unwrap();
+        assert_eq!(second.path(), "/b");
+        assert!(second.get_body().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_and_malformed_requests() {
+        let config = ServerConfig::new().max_header_size(64).max_body_size(16);
+
+        let raw = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(100));
+        let mut reader = RequestReader::new(raw.as_bytes(), &config);
+        let error = reader.read_request().await.unwrap_err();
+        assert!(matches!(error, ReadError::HeadersTooLargeError));
+        assert_eq!(error.status_code(), Some(431));
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 17\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(413));
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] = b"GET / HTTP/1.1\r\nBroken header\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] = b"GET / HTTP/1.1\r\nX-Bytes: \xff\xfe\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] = b"GET / HTTX/1.1\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] = b"GET / HTTP/3.0\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert!(matches!(error, ReadError::VersionNotSupportedError(_)));
+        assert_eq!(error.status_code(), Some(505));
+    }
+
+    #[tokio::test]
+    async fn test_decodes_chunked_body_with_trailers() {
+        let raw: &[u8] =
+            b"POST /stream HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n7\r\n, crabs\r\n0\r\nX-Checksum: abc\r\n\r\nGET /next HTTP/1.1\r\n\r\n";
+        let mut reader = RequestReader::new(raw, &ServerConfig::default());
+
+        let request = reader.read_request().await.unwrap().unwrap();
+        assert_eq!(request.get_body(), b"hello, crabs");
+        assert_eq!(request.get_trailers().get("X-Checksum").unwrap(), "abc");
+
+        let next = reader.read_request().await.unwrap().unwrap();
+        assert_eq!(next.path(), "/next");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_chunked_bodies() {
+        let config = ServerConfig::new().max_body_size(8);
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(413));
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] =
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 5\r\n\r\n0\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(400));
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n0\r\n\r\n";
+        let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+        assert_eq!(error.status_code(), Some(501));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_signed_lengths() {
+        let config = ServerConfig::default();
+        for raw in [
+            b"POST / HTTP/1.1\r\nContent-Length: +5\r\n\r\nhello".as_slice(),
+            b"POST / HTTP/1.1\r\nContent-Length: -0\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: 0x5\r\n\r\nhello",
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n+5\r\nhello\r\n0\r\n\r\n",
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\r\n",
+        ] {
+            let error = RequestReader::new(raw, &config).read_request().await.unwrap_err();
+            assert_eq!(error.status_code(), Some(400), "{}", String::from_utf8_lossy(raw));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use CrabServe::http_core::request::{HttpRequest, Request};
+    use CrabServe::http_core::request::{HttpRequest, Request, RequestError};
 
     #[test]
     fn test_request_setter_and_getters() {
@@ -63,7 +63,44 @@ mod tests {
         assert!(headers.contains_key("Content-Length"));
 
         // Assert body
-        let expected_body = "{\"name\":\"John Doe\",\"email\":\"john@example.com\"}".as_bytes().to_vec();
+        let expected_body = "{\"name\":\"John Doe\", \"email\":\"john@example.com\"}".as_bytes().to_vec();
         assert_eq!(request.get_body(), expected_body);
     }
+
+    #[tokio::test]
+    async fn test_parse_request_splits_and_decodes_target() {
+        let raw_request = "GET /files/crab%20photo.png?size=large&tag=a%26b&tag=c HTTP/1.1\r\n\r\n";
+        let request = Request::parse(raw_request).await.unwrap();
+
+        assert_eq!(request.path(), "/files/crab photo.png");
+        assert_eq!(request.raw_path(), "/files/crab%20photo.png");
+        assert_eq!(request.query(), "size=large&tag=a%26b&tag=c");
+
+        let params = request.query_params();
+        assert_eq!(params.get("size").unwrap(), "large");
+        assert_eq!(params.get("tag").unwrap(), "c");
+
+        assert!(Request::parse("GET /%ff HTTP/1.1\r\n\r\n").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_request_checks_version() {
+        assert_eq!(Request::parse("GET / HTTP/1.0\r\n\r\n").await.unwrap().version, "HTTP/1.0");
+        assert_eq!(Request::parse("GET / HTTP/1.1\r\n\r\n").await.unwrap().version, "HTTP/1.1");
+
+        for malformed in ["GET /", "GET / HTTP/", "GET / HTTP/1", "GET / HTTP/1.1.", "GET / HTTP/x.y", "GET / http/1.1", "GET / HTTP/1.1 extra"] {
+            let error = Request::parse(&format!("{}\r\n\r\n", malformed)).await.unwrap_err();
+            assert!(
+                matches!(error, RequestError::InvalidVersionError(_) | RequestError::RequestLineParseError),
+                "{}: {:?}",
+                malformed,
+                error
+            );
+        }
+
+        for unsupported in ["HTTP/2.0", "HTTP/3.0", "HTTP/0.9", "HTTP/1.2"] {
+            let error = Request::parse(&format!("GET / {}\r\n\r\n", unsupported)).await.unwrap_err();
+            assert!(matches!(error, RequestError::UnsupportedVersionError(_)), "{}: {:?}", unsupported, error);
+        }
+    }
 }
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+    use CrabServe::http_core::{ body::Body, response::Response };
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+    fn split_head(formatted: &[u8]) -> (String, &[u8]) {
+        let position = formatted
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        (String::from_utf8(formatted[..position].to_vec()).unwrap(), &formatted[position + 4..])
+    }
+
+    #[test]
+    fn test_format_keeps_binary_body_and_adds_headers() {
+        let response = Response::new(200)
+            .add_header("Content-Type", "image/png")
+            .add_body(PNG_SIGNATURE.to_vec());
+
+        let formatted = response.format().unwrap();
+        let (head, body) = split_head(&formatted);
+
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Content-Length: 8\r\n"));
+        assert!(head.contains("\r\nServer: CrabServer"));
+        assert!(head.contains("Date: "));
+        assert!(head.contains(" GMT\r\n"));
+        assert_eq!(body, PNG_SIGNATURE);
+    }
+
+    #[test]
+    fn test_format_respects_explicit_headers() {
+        let response = Response::new(200)
+            .add_header("Server", "Custom")
+            .add_header("Date", "Thu, 01 Jan 1970 00:00:00 GMT")
+            .add_body(b"abc".to_vec());
+
+        let (head, _) = split_head(&response.format().unwrap());
+        assert!(head.contains("\r\nServer: Custom"));
+        assert_eq!(head.matches("Server:").count(), 1);
+        assert_eq!(head.matches("Date:").count(), 1);
+
+        let formatted = Response::new(204).format().unwrap();
+        let (head, body) = split_head(&formatted);
+        assert!(!head.contains("Content-Length"));
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_to_matches_format() {
+        let response = Response::new(201)
+            .add_header("Date", "Thu, 01 Jan 1970 00:00:00 GMT")
+            .add_body(PNG_SIGNATURE.to_vec());
+        let mut written = Vec::new();
+
+        response.write_to(&mut written).await.unwrap();
+        assert_eq!(written, response.format().unwrap());
+
+        let response = response.chunked();
+        let mut written = Vec::new();
+        response.write_to(&mut written).await.unwrap();
+
+        let (head, body) = split_head(&written);
+        assert!(head.contains("Transfer-Encoding: chunked"));
+        assert!(!head.contains("Content-Length"));
+        assert_eq!(body, [b"8\r\n".as_slice(), &PNG_SIGNATURE, b"\r\n0\r\n\r\n"].concat());
+    }
+
+    #[tokio::test]
+    async fn test_write_to_streams_chunks() {
+        let chunks = stream::iter(["id,name\n", "1,crab\n", "", "2,lobster\n"].map(|chunk| Ok::<_, std::io::Error>(chunk.as_bytes().to_vec())));
+        let response = Response::new(200)
+            .add_header("Content-Type", "text/csv")
+            .add_stream_body(chunks)
+            .chunked();
+        assert!(response.body.is_stream());
+        assert_eq!(response.body.len(), None);
+
+        let mut written = Vec::new();
+        response.write_to(&mut written).await.unwrap();
+        let (head, body) = split_head(&written);
+        assert!(!head.contains("Content-Length"));
+        assert_eq!(body, b"8\r\nid,name\n\r\n7\r\n1,crab\n\r\na\r\n2,lobster\n\r\n0\r\n\r\n");
+
+        // The chunks were taken by the first write, also from clones of the response.
+        let mut written = Vec::new();
+        response.clone().write_to(&mut written).await.unwrap();
+        assert!(written.ends_with(b"\r\n\r\n0\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_must_match_content_length() {
+        let stream_of = |chunks: &'static [&'static str]| {
+            Body::stream(stream::iter(chunks.iter().map(|chunk| Ok::<_, std::io::Error>(chunk.as_bytes().to_vec()))))
+        };
+
+        let mut response = Response::new(200).add_header("Content-Length", "6");
+        response.body = stream_of(&["abc", "def"]);
+        let mut written = Vec::new();
+        response.write_to(&mut written).await.unwrap();
+        assert!(written.ends_with(b"\r\n\r\nabcdef"));
+
+        response.body = stream_of(&["abc"]);
+        assert!(response.write_to(&mut Vec::new()).await.is_err());
+
+        response.body = stream_of(&["abc", "defg"]);
+        assert!(response.write_to(&mut Vec::new()).await.is_err());
+
+        let failing = stream::iter([Ok(b"partial".to_vec()), Err(std::io::Error::other("query failed"))]);
+        let response = Response::new(200).add_stream_body(failing).chunked();
+        let mut written = Vec::new();
+        assert!(response.write_to(&mut written).await.is_err());
+        assert!(!written.ends_with(b"0\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_file_body_clones_read_independently() {
+        let contents: Vec<u8> = (0..200_000u32).map(|n| (n % 251) as u8).collect();
+        let mut file = tempfile::tempfile().unwrap();
+        std::io::Write::write_all(&mut file, &contents).unwrap();
+
+        // Several chunks long, so the two writes interleave their reads.
+        let response = Response::new(200).add_file_body(file, 10, 150_000);
+        let copy = response.clone();
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        let (a, b) = tokio::join!(response.write_to(&mut first), copy.write_to(&mut second));
+        a.unwrap();
+        b.unwrap();
+
+        for written in [first, second] {
+            let (_, body) = split_head(&written);
+            assert_eq!(body, &contents[10..150_010]);
+        }
+    }
+
+    #[test]
+    fn test_format_fails_for_bodies_it_cannot_hold() {
+        let mut file = tempfile::tempfile().unwrap();
+        std::io::Write::write_all(&mut file, b"hello crab").unwrap();
+        let response = Response::new(200).add_file_body(file.try_clone().unwrap(), 6, 4);
+        let formatted = response.format().unwrap();
+        let (head, body) = split_head(&formatted);
+        assert!(head.contains("Content-Length: 4\r\n"));
+        assert_eq!(body, b"crab");
+
+        // Reading past the end would contradict the announced Content-Length.
+        let response = Response::new(200).add_file_body(file, 6, 10);
+        assert!(response.format().is_err());
+
+        let chunks = stream::iter([Ok::<_, std::io::Error>(b"later".to_vec())]);
+        assert!(Response::new(200).add_stream_body(chunks).format().is_err());
+    }
+}
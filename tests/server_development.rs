@@ -2,7 +2,31 @@
 mod tests {
     use std::{ net::SocketAddr, sync::Arc };
     use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream, sync::{ oneshot, Mutex } };
-    use CrabServe::{ database::mongodb::MongoDB, database::db::Database, server::{ CrabServer, Server } };
+    use CrabServe::{
+        config::config::ServerConfig,
+        database::mongodb::MongoDB,
+        database::db::Database,
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request }, response::Response },
+        router::{ handler::State, middleware::Next, router::Router },
+        server::{ CrabServer, Server },
+    };
+
+    async fn hello_world() -> Response {
+        Response::new(200).add_body(b"Hello, World!".to_vec())
+    }
+
+    async fn create_user() -> Response {
+        Response::new(201).add_body(b"Created".to_vec())
+    }
+
+    async fn send_request(addr: &str, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut buffer = [0; 1024];
+        let n = stream.read(&mut buffer).await.unwrap();
+        String::from_utf8_lossy(&buffer[..n]).to_string()
+    }
 
     #[tokio::test]
     async fn test_server_addr() {
@@ -22,7 +46,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_server_run() {
-        let server = CrabServer::new([127, 0, 0, 1], 3030);
+        let server = CrabServer::new([127, 0, 0, 1], 3030).add_router(
+            Router::new("/".to_string()).add_route(HttpMethods::GET, "/", hello_world)
+        );
         let server_task = tokio::spawn(async move {
             server
                 .run(
@@ -50,6 +76,155 @@ mod tests {
         server_task.abort();
     }
 
+    #[tokio::test]
+    async fn test_server_dispatches_through_routers() {
+        let server = CrabServer::new([127, 0, 0, 1], 3031)
+            .add_router(
+                Router::new("/".to_string())
+                    .add_route(HttpMethods::GET, "/", hello_world)
+                    .add_route(HttpMethods::GET, "/api/health", hello_world)
+            )
+            .add_router(
+                Router::new("/api".to_string())
+                    .add_route(HttpMethods::POST, "/users", create_user)
+                    .add_error_handler(404, || Response::new(404).add_body(b"No such API".to_vec()))
+            );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let response = send_request("127.0.0.1:3031", "POST /api/users HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 201 Created"));
+
+        let response = send_request("127.0.0.1:3031", "GET /api/users HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("405"));
+
+        // The `/api` router has no such route, so the shorter prefix gets a turn.
+        let response = send_request("127.0.0.1:3031", "GET /api/health HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 200 OK"));
+
+        let response = send_request("127.0.0.1:3031", "GET /api/missing HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 404 Not Found"));
+        assert!(response.contains("No such API"));
+
+        let response = send_request("127.0.0.1:3031", "GET /missing HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 404 Not Found"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_server_rejects_oversized_body() {
+        let server = CrabServer::new([127, 0, 0, 1], 3034)
+            .with_config(ServerConfig::new().max_body_size(1024))
+            .add_router(
+                Router::new("/".to_string()).add_route(HttpMethods::POST, "/upload", |request: Request| async move {
+                    format!("{} bytes", request.get_body().len())
+                })
+            );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let body = "a".repeat(1024);
+        let request = format!("POST /upload HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let response = send_request("127.0.0.1:3034", &request).await;
+        assert!(response.contains("HTTP/1.1 200 OK"));
+        assert!(response.contains("1024 bytes"));
+
+        let request = "POST /upload HTTP/1.1\r\nContent-Length: 1025\r\n\r\n";
+        let response = send_request("127.0.0.1:3034", request).await;
+        assert!(response.starts_with("HTTP/1.1 413"));
+
+        server_task.abort();
+    }
+
+    #[derive(Clone)]
+    struct Greeting(String);
+
+    async fn greet_user(request: Request, State(greeting): State<Greeting>) -> String {
+        format!("{}, user {}", greeting.0, request.param("id").unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_async_handlers_receive_request_and_state() {
+        let server = CrabServer::new([127, 0, 0, 1], 3032).add_router(
+            Router::new("/".to_string())
+                .with_state(Greeting("Hello".to_string()))
+                .add_route(HttpMethods::GET, "/users/:id", greet_user)
+                .add_route(HttpMethods::GET, "/status", || async { (503, "Maintenance") })
+        );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let response = send_request("127.0.0.1:3032", "GET /users/42 HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 200 OK"));
+        assert!(response.contains("Hello, user 42"));
+
+        let response = send_request("127.0.0.1:3032", "GET /status HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 503"));
+        assert!(response.contains("Maintenance"));
+
+        server_task.abort();
+    }
+
+    async fn trace(mut request: Request, next: Next, name: &str) -> Response {
+        let trace = match request.get_headers().get("X-Trace") {
+            Some(trace) => format!("{},{}", trace, name),
+            None => name.to_string(),
+        };
+        request.add_header("X-Trace", &trace);
+        next.run(request).await
+    }
+
+    async fn echo_trace(request: Request) -> String {
+        request.get_headers().get_str("X-Trace").unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_middleware_ordering_and_short_circuit() {
+        let server = CrabServer::new([127, 0, 0, 1], 3033)
+            .add_middleware(|request, next: Next| async move {
+                let response = trace(request, next, "global").await;
+                response.add_header("X-Powered-By", "CrabServer")
+            })
+            .add_router(
+                Router::new("/".to_string())
+                    .add_route(HttpMethods::GET, "/trace", echo_trace)
+                    .add_route(HttpMethods::GET, "/admin", echo_trace)
+                    .add_middleware(|request, next| trace(request, next, "router"))
+                    .add_route_middleware(HttpMethods::GET, "/trace", |request, next| {
+                        trace(request, next, "route")
+                    })
+                    .add_route_middleware(HttpMethods::GET, "/admin", |_, _| async {
+                        Response::new(401).add_body(b"Unauthorized".to_vec())
+                    })
+            );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let response = send_request("127.0.0.1:3033", "GET /trace HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 200 OK"));
+        assert!(response.contains("X-Powered-By: CrabServer"));
+        assert!(response.ends_with("global,router,route"));
+
+        let response = send_request("127.0.0.1:3033", "GET /admin HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("HTTP/1.1 401 Unauthorized"));
+        assert!(response.contains("X-Powered-By: CrabServer"));
+
+        server_task.abort();
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn test_mongodb_connection_with_server_running() {
@@ -94,6 +269,7 @@ mod tests {
         });
 
 
+        #[allow(clippy::let_unit_value)]
         let _ = server_handler.await.expect("Server handler failed to run");
 
         let connected = db_connection_status.lock().await;
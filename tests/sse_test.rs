@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures_util::stream;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request }, sse::{ Event, LastEventId, Sse } },
+        router::{ handler::{ FromRequest, IntoResponse }, router::Router },
+        server::{ CrabServer, Server },
+    };
+
+    // Counts up from the event after `Last-Event-ID`, pausing between events.
+    fn router() -> Router {
+        Router::new("/".to_string()).add_route(HttpMethods::GET, "/events", |last: LastEventId| async move {
+            let start = last.0.and_then(|id| id.parse::<u32>().ok()).map_or(1, |id| id + 1);
+            let events = stream::unfold(start, |n| async move {
+                if n > 3 {
+                    return None;
+                }
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                Some((Event::new().event("tick").id(n.to_string()).data(n.to_string()), n + 1))
+            });
+            Sse::new(events).keep_alive(Duration::from_millis(100))
+        })
+    }
+
+    async fn read_until(stream: &mut TcpStream, received: &mut String, pattern: &str) {
+        let mut buffer = [0; 1024];
+        while !received.contains(pattern) {
+            let n = stream.read(&mut buffer).await.unwrap();
+            assert!(n > 0, "connection closed before {:?} arrived: {:?}", pattern, received);
+            received.push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = Event::new()
+            .comment("hello")
+            .event("update")
+            .id("42")
+            .retry(Duration::from_secs(3))
+            .data("first\r\nsecond\nthird");
+        assert_eq!(
+            String::from_utf8(event.to_bytes()).unwrap(),
+            ": hello\nevent: update\nid: 42\nretry: 3000\ndata: first\ndata: second\ndata: third\n\n"
+        );
+
+        let event = Event::new().json_data(&serde_json::json!({ "ok": true })).unwrap();
+        assert_eq!(event.to_bytes(), b"data: {\"ok\":true}\n\n");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_event_id_rejects_line_breaks() {
+        Event::new().id("1\ndata: injected");
+    }
+
+    #[tokio::test]
+    async fn test_sse_response_headers() {
+        let response = Sse::new(stream::empty::<Event>()).into_response();
+        assert_eq!(response.headers().get_str("Content-Type"), Some("text/event-stream"));
+        assert_eq!(response.headers().get_str("Cache-Control"), Some("no-cache"));
+        assert!(response.is_chunked());
+
+        let mut request = Request::new("GET", "/events");
+        request.add_header("Last-Event-ID", "7");
+        assert_eq!(LastEventId::from_request(&request).await.unwrap(), LastEventId(Some("7".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_streams_events_with_keep_alive() {
+        let server = CrabServer::new([127, 0, 0, 1], 3110).add_router(router());
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3110").await.unwrap();
+        stream.write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+
+        // The first event arrives while the handler is still producing the rest.
+        let mut received = String::new();
+        read_until(&mut stream, &mut received, "data: 1\n\n").await;
+        assert!(received.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(received.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!received.contains("data: 2"));
+
+        read_until(&mut stream, &mut received, "0\r\n\r\n").await;
+        assert!(received.contains(": keep-alive\n\n"));
+        assert!(received.contains("event: tick\nid: 3\ndata: 3\n\n"));
+
+        // A reconnecting client picks up after the last event it saw.
+        stream.write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\nLast-Event-ID: 2\r\n\r\n").await.unwrap();
+        let mut received = String::new();
+        read_until(&mut stream, &mut received, "0\r\n\r\n").await;
+        assert!(!received.contains("id: 2\n"));
+        assert!(received.contains("id: 3\ndata: 3\n\n"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_streams_events_over_http2() {
+        let server = CrabServer::new([127, 0, 0, 1], 3111).add_router(router());
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let socket = TcpStream::connect("127.0.0.1:3111").await.unwrap();
+        let (client, connection) = h2::client::handshake(socket).await.unwrap();
+        tokio::spawn(connection);
+        let mut client = client.ready().await.unwrap();
+
+        let request = http::Request::get("http://127.0.0.1:3111/events").body(()).unwrap();
+        let response = client.send_request(request, true).unwrap().0.await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        assert!(response.headers().get("transfer-encoding").is_none());
+
+        let mut body = response.into_body();
+        // The keep-alive interval runs out before the first event is ready.
+        let first = body.data().await.unwrap().unwrap();
+        assert_eq!(&first[..], b": keep-alive\n\n");
+
+        let mut rest = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            let _ = body.flow_control().release_capacity(chunk.len());
+            rest.extend_from_slice(&chunk);
+        }
+        assert!(String::from_utf8(rest).unwrap().ends_with("id: 3\ndata: 3\n\n"));
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        http_core::{ request::{ HttpRequest, Request }, response::Response },
+        router::{ router::Router, static_files::StaticFiles },
+        server::{ CrabServer, Server },
+    };
+
+    fn site(root: &Path) -> Router {
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("index.html"), "<h1>Home</h1>").unwrap();
+        std::fs::write(root.join("docs/index.html"), "<h1>Docs</h1>").unwrap();
+        std::fs::write(root.join("app.css"), "body { color: red; }").unwrap();
+        std::fs::write(root.join("crab.png"), b"0123456789").unwrap();
+
+        Router::new("/".to_string()).add_static_files("/assets", StaticFiles::new(root))
+    }
+
+    async fn get(router: &Router, path: &str, headers: &[(&str, &str)]) -> Response {
+        let mut request = Request::new("GET", path);
+        for (name, value) in headers {
+            request.add_header(name, value);
+        }
+        router.handle(request).await
+    }
+
+    fn body(response: &Response) -> String {
+        let formatted = String::from_utf8(response.format().unwrap()).unwrap();
+        formatted.split_once("\r\n\r\n").unwrap().1.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_serves_files_and_indexes() {
+        let root = tempfile::tempdir().unwrap();
+        let router = site(root.path());
+
+        let response = get(&router, "/assets/app.css", &[]).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.headers().get_str("Content-Type"), Some("text/css"));
+        assert_eq!(response.content_length(), 20);
+        assert_eq!(body(&response), "body { color: red; }");
+
+        let response = get(&router, "/assets", &[]).await;
+        assert_eq!(response.status_code(), 301);
+        assert_eq!(response.headers().get_str("Location"), Some("/assets/"));
+
+        let response = get(&router, "/assets/", &[]).await;
+        assert_eq!(response.headers().get_str("Content-Type"), Some("text/html; charset=utf-8"));
+        assert_eq!(body(&response), "<h1>Home</h1>");
+
+        let response = get(&router, "/assets/docs/", &[]).await;
+        assert_eq!(body(&response), "<h1>Docs</h1>");
+
+        assert_eq!(get(&router, "/assets/missing.txt", &[]).await.status_code(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_directory_redirect_encodes_location() {
+        let root = tempfile::tempdir().unwrap();
+        let router = site(root.path());
+        std::fs::create_dir(root.path().join("café ?%")).unwrap();
+
+        let request = Request::parse("GET /assets/caf%C3%A9%20%3F%25?v=1 HTTP/1.1\r\n\r\n").await.unwrap();
+        let response = router.handle(request).await;
+        assert_eq!(response.status_code(), 301);
+        assert_eq!(response.headers().get_str("Location"), Some("/assets/caf%C3%A9%20%3F%25/?v=1"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_traversal() {
+        let root = tempfile::tempdir().unwrap();
+        let public = root.path().join("public");
+        std::fs::write(root.path().join("secret.txt"), "secret").unwrap();
+        let router = site(&public);
+
+        let request = Request::parse("GET /assets/%2e%2e/secret.txt HTTP/1.1\r\n\r\n").await.unwrap();
+        assert_eq!(router.handle(request).await.status_code(), 404);
+        assert_eq!(get(&router, "/assets/..%5Csecret.txt", &[]).await.status_code(), 404);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.path().join("secret.txt"), public.join("link.txt")).unwrap();
+            assert_eq!(get(&router, "/assets/link.txt", &[]).await.status_code(), 404);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ranges() {
+        let root = tempfile::tempdir().unwrap();
+        let router = site(root.path());
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=2-5")]).await;
+        assert_eq!(response.status_code(), 206);
+        assert_eq!(response.headers().get_str("Content-Range"), Some("bytes 2-5/10"));
+        assert_eq!(body(&response), "2345");
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=-3")]).await;
+        assert_eq!(body(&response), "789");
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=7-")]).await;
+        assert_eq!(response.headers().get_str("Content-Range"), Some("bytes 7-9/10"));
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=10-20")]).await;
+        assert_eq!(response.status_code(), 416);
+        assert_eq!(response.headers().get_str("Content-Range"), Some("bytes */10"));
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=0-1,4-5")]).await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = get(&router, "/assets/crab.png", &[("Range", "bytes=0-1"), ("If-Range", "\"stale\"")]).await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(body(&response), "0123456789");
+    }
+
+    #[tokio::test]
+    async fn test_conditional_requests() {
+        let root = tempfile::tempdir().unwrap();
+        let router = site(root.path());
+
+        let response = get(&router, "/assets/app.css", &[]).await;
+        let etag = response.headers().get_str("ETag").unwrap().to_string();
+        let last_modified = response.headers().get_str("Last-Modified").unwrap().to_string();
+
+        let response = get(&router, "/assets/app.css", &[("If-None-Match", &etag)]).await;
+        assert_eq!(response.status_code(), 304);
+        assert_eq!(response.headers().get_str("ETag"), Some(etag.as_str()));
+        assert_eq!(response.content_length(), 0);
+
+        let weak = format!("\"other\", W/{}", etag);
+        assert_eq!(get(&router, "/assets/app.css", &[("If-None-Match", &weak)]).await.status_code(), 304);
+
+        let response = get(&router, "/assets/app.css", &[("If-Modified-Since", &last_modified)]).await;
+        assert_eq!(response.status_code(), 304);
+
+        let response = get(&router, "/assets/app.css", &[("If-None-Match", "\"other\"")]).await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = get(&router, "/assets/app.css", &[("If-Match", "\"other\"")]).await;
+        assert_eq!(response.status_code(), 412);
+    }
+
+    #[tokio::test]
+    async fn test_streams_large_files() {
+        let root = tempfile::tempdir().unwrap();
+        let contents: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        std::fs::write(root.path().join("large.bin"), &contents).unwrap();
+
+        let server = CrabServer::new([127, 0, 0, 1], 3070).add_router(
+            Router::new("/".to_string()).add_static_files("/files", StaticFiles::new(root.path()))
+        );
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3070").await.unwrap();
+        stream.write_all(b"GET /files/large.bin HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let head_end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..head_end]);
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains("Content-Type: application/octet-stream"));
+        assert!(head.contains(&format!("Content-Length: {}", contents.len())));
+        assert_eq!(&response[head_end + 4..], contents.as_slice());
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{ atomic::{ AtomicUsize, Ordering }, Arc };
+    use futures_util::stream;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, response::Response },
+        router::router::Router,
+        server::{ CrabServer, Server },
+    };
+
+    const CHUNK_SIZE: usize = 64 * 1024;
+    const CHUNKS: usize = 4096;
+
+    // A 256 MiB export that records how many chunks have been generated.
+    fn router(generated: Arc<AtomicUsize>) -> Router {
+        Router::new("/".to_string()).add_route(HttpMethods::GET, "/export", move || {
+            let generated = generated.clone();
+            async move {
+                let rows = stream::iter(
+                    (0..CHUNKS).map(move |_| {
+                        generated.fetch_add(1, Ordering::SeqCst);
+                        Ok::<_, std::io::Error>(vec![b'x'; CHUNK_SIZE])
+                    })
+                );
+                Response::new(200).add_header("Content-Type", "text/csv").add_stream_body(rows)
+            }
+        })
+    }
+
+    async fn start(port: u16, generated: Arc<AtomicUsize>) -> tokio::task::JoinHandle<()> {
+        let server = CrabServer::new([127, 0, 0, 1], port).add_router(router(generated));
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        server_task
+    }
+
+    #[tokio::test]
+    async fn test_stream_waits_for_slow_client() {
+        let generated = Arc::new(AtomicUsize::new(0));
+        let server_task = start(3120, generated.clone()).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3120").await.unwrap();
+        stream.write_all(b"GET /export HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+
+        // Nothing is read, so generation stalls once the socket buffers are full.
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let stalled = generated.load(Ordering::SeqCst);
+        assert!(stalled > 0 && stalled < CHUNKS / 4, "generated {} chunks", stalled);
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        let head = String::from_utf8(head).unwrap();
+        assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!head.contains("Content-Length"));
+
+        let mut received = 0;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        while generated.load(Ordering::SeqCst) < CHUNKS {
+            received += stream.read(&mut buffer).await.unwrap();
+        }
+        assert!(received > CHUNKS / 2 * CHUNK_SIZE);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_http10_client_ends_with_close() {
+        let generated = Arc::new(AtomicUsize::new(0));
+        let server_task = start(3121, generated).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3121").await.unwrap();
+        stream.write_all(b"GET /export HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let position = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..position]);
+        assert!(head.contains("Connection: close\r\n"));
+        assert!(!head.contains("Transfer-Encoding"));
+        assert_eq!(response.len() - position - 4, CHUNKS * CHUNK_SIZE);
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use tokio_rustls::{ rustls, TlsConnector };
+    use CrabServe::{
+        http_core::http_types::HttpMethods,
+        router::router::Router,
+        server::{ CrabServer, Server },
+    };
+
+    struct Identity {
+        certificate: String,
+        private_key: String,
+        der: Vec<u8>,
+    }
+
+    fn identity(name: &str) -> Identity {
+        let generated = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        // Every serialization signs anew, so the DER is taken from the PEM that is served.
+        let certificate = generated.serialize_pem().unwrap();
+        let der = rustls_pemfile::certs(&mut certificate.as_bytes()).unwrap().remove(0);
+        Identity {
+            certificate,
+            private_key: generated.serialize_private_key_pem(),
+            der,
+        }
+    }
+
+    fn connector(trusted: &[&Identity]) -> TlsConnector {
+        let mut roots = rustls::RootCertStore::empty();
+        for identity in trusted {
+            roots.add(&rustls::Certificate(identity.der.clone())).unwrap();
+        }
+        let config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        TlsConnector::from(Arc::new(config))
+    }
+
+    // Returns the certificate the server presented and the response it sent.
+    async fn get(connector: &TlsConnector, server_name: &str, path: &str) -> (Vec<u8>, String) {
+        let socket = TcpStream::connect("127.0.0.1:3080").await.unwrap();
+        let server_name = rustls::ServerName::try_from(server_name).unwrap();
+        let mut stream = connector.connect(server_name, socket).await.unwrap();
+        let presented = stream.get_ref().1.peer_certificates().unwrap()[0].0.clone();
+
+        let request = format!("GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        (presented, response)
+    }
+
+    #[tokio::test]
+    async fn test_tls_with_sni_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let alpha = identity("alpha.test");
+        let beta = identity("beta.test");
+        let wild = identity("*.wild.test");
+        let certificate_path = dir.path().join("alpha.pem");
+        let private_key_path = dir.path().join("alpha.key");
+        std::fs::write(&certificate_path, &alpha.certificate).unwrap();
+        std::fs::write(&private_key_path, &alpha.private_key).unwrap();
+
+        let server = CrabServer::new([127, 0, 0, 1], 3080)
+            .add_router(
+                Router::new("/alpha".to_string())
+                    .with_ssl(certificate_path.to_str().unwrap(), private_key_path.to_str().unwrap())
+                    .add_route(HttpMethods::GET, "/", || async { "alpha" })
+            )
+            .add_router(
+                Router::new("/beta".to_string())
+                    .with_ssl_pem(beta.certificate.as_bytes(), beta.private_key.as_bytes())
+                    .add_route(HttpMethods::GET, "/", || async { "beta" })
+            )
+            .add_router(
+                Router::new("/wild".to_string())
+                    .with_ssl_pem(wild.certificate.as_bytes(), wild.private_key.as_bytes())
+                    .add_route(HttpMethods::GET, "/", || async { "wild" })
+            );
+        let certificates = server.certificates();
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        let (presented, response) = get(&connector(&[&alpha, &beta]), "alpha.test", "/alpha").await;
+        assert_eq!(presented, alpha.der);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("alpha"));
+
+        let (presented, response) = get(&connector(&[&alpha, &beta]), "beta.test", "/beta").await;
+        assert_eq!(presented, beta.der);
+        assert!(response.ends_with("beta"));
+
+        let (presented, response) = get(&connector(&[&alpha, &wild]), "API.wild.test", "/wild").await;
+        assert_eq!(presented, wild.der);
+        assert!(response.ends_with("wild"));
+
+        // A name no certificate covers gets the first router's certificate, which the client rejects.
+        let socket = TcpStream::connect("127.0.0.1:3080").await.unwrap();
+        let server_name = rustls::ServerName::try_from("gamma.test").unwrap();
+        assert!(connector(&[&alpha, &beta]).connect(server_name, socket).await.is_err());
+
+        let renewed = identity("alpha.test");
+        std::fs::write(&certificate_path, &renewed.certificate).unwrap();
+        std::fs::write(&private_key_path, &renewed.private_key).unwrap();
+        certificates.reload().unwrap();
+
+        let (presented, _) = get(&connector(&[&renewed, &beta]), "alpha.test", "/alpha").await;
+        assert_eq!(presented, renewed.der);
+
+        server_task.abort();
+    }
+
+    #[test]
+    fn test_rejects_invalid_pem() {
+        let router = Router::new("/".to_string()).with_ssl_pem(b"not a certificate".to_vec(), b"".to_vec());
+        let certificates = CrabServer::new([127, 0, 0, 1], 0).add_router(router).certificates();
+        assert!(certificates.reload().is_err());
+    }
+
+    #[test]
+    fn test_debug_output_redacts_private_key() {
+        let identity = identity("alpha.test");
+        let router = Router::new("/".to_string()).with_ssl_pem(identity.certificate.as_bytes(), identity.private_key.as_bytes());
+        let server = CrabServer::new([127, 0, 0, 1], 0).add_router(router.clone());
+
+        let key_body = identity.private_key.lines().nth(1).unwrap();
+        for printed in [format!("{:?}", router), format!("{:?}", server)] {
+            assert!(printed.contains("<redacted>"));
+            assert!(!printed.contains(key_body));
+            assert!(!printed.contains("45, 45, 45, 45, 45"));
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use CrabServe::get;
+
+#[get("/health")]
+fn health() -> &'static str {
+    "ok"
+}
+
+fn main() {}
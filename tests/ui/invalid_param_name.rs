@@ -0,0 +1,8 @@
+use CrabServe::post;
+
+#[post("/users/:user-id")]
+async fn update_user() -> &'static str {
+    "updated"
+}
+
+fn main() {}
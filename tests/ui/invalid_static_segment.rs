@@ -0,0 +1,8 @@
+use CrabServe::get;
+
+#[get("/search?q")]
+async fn search() -> &'static str {
+    "results"
+}
+
+fn main() {}
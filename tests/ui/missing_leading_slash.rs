@@ -0,0 +1,8 @@
+use CrabServe::get;
+
+#[get("users")]
+async fn list_users() -> &'static str {
+    "users"
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use CrabServe::get;
+
+#[get("/posts/:page?/comments")]
+async fn comments() -> &'static str {
+    "comments"
+}
+
+fn main() {}
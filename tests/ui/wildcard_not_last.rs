@@ -0,0 +1,8 @@
+use CrabServe::get;
+
+#[get("/files/*rest/meta")]
+async fn file_meta() -> &'static str {
+    "meta"
+}
+
+fn main() {}
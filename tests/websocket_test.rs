@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use futures_util::{ SinkExt, StreamExt };
+    use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::TcpStream };
+    use tokio_util::codec::{ Decoder, Encoder };
+    use CrabServe::{
+        http_core::{ http_types::HttpMethods, request::{ HttpRequest, Request } },
+        router::router::Router,
+        server::{ CrabServer, Server },
+        websocket::{
+            deflate::PerMessageDeflate,
+            frame::{ Frame, FrameCodec, OpCode, Role },
+            socket::{ CloseCode, Message, WebSocket, WebSocketConfig },
+            upgrade::{ accept_key, WebSocketUpgrade },
+        },
+    };
+
+    const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+    fn router(config: WebSocketConfig) -> Router {
+        Router::new("/".to_string())
+            .with_state(config)
+            .add_route(HttpMethods::GET, "/echo", |ws: WebSocketUpgrade| async move {
+                ws.protocols(&["chat"]).on_upgrade(|mut sender, mut receiver| async move {
+                    while let Some(Ok(message)) = receiver.next().await {
+                        if matches!(message, Message::Text(_) | Message::Binary(_)) {
+                            sender.send(message).await.unwrap();
+                        }
+                    }
+                })
+            })
+    }
+
+    async fn start(port: u16, config: WebSocketConfig) -> tokio::task::JoinHandle<()> {
+        let server = CrabServer::new([127, 0, 0, 1], port).add_router(router(config));
+        let server_task = tokio::spawn(async move {
+            server.run(None, |_| {}, None).await.unwrap()
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        server_task
+    }
+
+    // Sends the opening handshake and returns the response head.
+    async fn handshake(stream: &mut TcpStream, extra_headers: &str) -> String {
+        let request = format!(
+            "GET /echo HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: {}\r\n{}\r\n",
+            KEY,
+            extra_headers
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        String::from_utf8(head).unwrap()
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // The example from RFC 6455 section 1.3.
+        assert_eq!(accept_key(KEY), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_frame_codec_masks_client_frames() {
+        let mut buffer = BytesMut::new();
+        FrameCodec::new(Role::Client, 1024).encode(Frame::new(OpCode::Text, b"Hello".to_vec()), &mut buffer).unwrap();
+        assert_eq!(buffer[0], 0x81);
+        assert_eq!(buffer[1], 0x80 | 5);
+        assert_ne!(&buffer[6..], b"Hello");
+
+        let frame = FrameCodec::new(Role::Server, 1024).decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(frame, Frame::new(OpCode::Text, b"Hello".to_vec()));
+        assert!(buffer.is_empty());
+
+        let mut unmasked = BytesMut::from(&[0x81, 0x02, b'h', b'i'][..]);
+        assert!(FrameCodec::new(Role::Server, 1024).decode(&mut unmasked).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_handshakes() {
+        let mut request = Request::new("GET", "/echo");
+        request.add_header("Connection", "Upgrade");
+        request.add_header("Upgrade", "websocket");
+        request.add_header("Sec-WebSocket-Version", "8");
+        request.add_header("Sec-WebSocket-Key", KEY);
+        let response = router(WebSocketConfig::new()).handle(request).await;
+        assert_eq!(response.status_code(), 426);
+        assert_eq!(response.headers().get_str("Sec-WebSocket-Version"), Some("13"));
+
+        let mut request = Request::new("GET", "/echo");
+        request.add_header("Connection", "Upgrade");
+        request.add_header("Upgrade", "websocket");
+        request.add_header("Sec-WebSocket-Version", "13");
+        request.add_header("Sec-WebSocket-Key", "too short");
+        assert_eq!(router(WebSocketConfig::new()).handle(request).await.status_code(), 400);
+
+        assert_eq!(router(WebSocketConfig::new()).handle(Request::new("GET", "/echo")).await.status_code(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_echo_fragmentation_ping_and_close() {
+        // Small frames make both ends fragment their messages.
+        let server_task = start(3100, WebSocketConfig::new().max_frame_size(4)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3100").await.unwrap();
+        let head = handshake(&mut stream, "Sec-WebSocket-Protocol: superchat, chat\r\n").await;
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert!(head.contains("Sec-WebSocket-Protocol: chat\r\n"));
+        assert!(!head.contains("Sec-WebSocket-Extensions"));
+
+        let mut socket = WebSocket::new(stream, Role::Client, WebSocketConfig::new().max_frame_size(4));
+        socket.send(Message::text("hello, crab")).await.unwrap();
+        assert_eq!(socket.recv().await.unwrap().unwrap(), Message::text("hello, crab"));
+
+        socket.send(Message::Binary(vec![1, 2, 3])).await.unwrap();
+        assert_eq!(socket.recv().await.unwrap().unwrap(), Message::Binary(vec![1, 2, 3]));
+
+        socket.send(Message::Ping(b"are you there".to_vec())).await.unwrap();
+        assert_eq!(socket.recv().await.unwrap().unwrap(), Message::Pong(b"are you there".to_vec()));
+
+        socket.send(Message::close(CloseCode::NORMAL, "bye")).await.unwrap();
+        match socket.recv().await.unwrap().unwrap() {
+            Message::Close(Some(close)) => assert_eq!(close.code, CloseCode::NORMAL),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+        assert!(socket.recv().await.is_none());
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_permessage_deflate() {
+        let server_task = start(3101, WebSocketConfig::new().permessage_deflate(true)).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3101").await.unwrap();
+        let head = handshake(
+            &mut stream,
+            "Sec-WebSocket-Extensions: permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits; server_no_context_takeover\r\n"
+        ).await;
+        let extensions = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Sec-WebSocket-Extensions: "))
+            .unwrap();
+        assert_eq!(extensions, "permessage-deflate; server_no_context_takeover");
+
+        let params = PerMessageDeflate::parse(extensions).unwrap();
+        let mut socket = WebSocket::new(stream, Role::Client, WebSocketConfig::new()).with_permessage_deflate(&params);
+        let text = "crab ".repeat(1000);
+        for _ in 0..2 {
+            socket.send(Message::text(text.clone())).await.unwrap();
+            assert_eq!(socket.recv().await.unwrap().unwrap(), Message::text(text.clone()));
+        }
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error_closes_connection() {
+        let server_task = start(3102, WebSocketConfig::new()).await;
+
+        let mut stream = TcpStream::connect("127.0.0.1:3102").await.unwrap();
+        handshake(&mut stream, "").await;
+
+        // Client frames must be masked; the server answers with close code 1002.
+        stream.write_all(&[0x81, 0x02, b'h', b'i']).await.unwrap();
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, [0x88, 0x02, 0x03, 0xea]);
+
+        server_task.abort();
+    }
+}